@@ -1,22 +1,35 @@
-use std::collections::HashMap;
+use adventofcode_2024::{Args, ParseError, parse_columns};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const DEFAULT_BUCKET_WIDTH: u64 = 500;
+const QUANTILES: [f64; 4] = [0.1, 0.25, 0.75, 0.9];
 
 fn main() {
+    let args = Args::from_env();
+    if args.flag("--analyze") {
+        let input = match args.value("--file") {
+            Some(path) => std::fs::read_to_string(path).unwrap(),
+            None => INPUT.to_string(),
+        };
+        let bucket_width = args
+            .value("--bucket-width")
+            .map_or(DEFAULT_BUCKET_WIDTH, |width| width.parse().unwrap());
+        match parse_input(&input) {
+            Ok((left, right)) => print_analysis(&Analysis::new(left, right), bucket_width),
+            Err(error) => eprintln!("{error}"),
+        }
+    }
     println!("{}", part_1(INPUT));
     println!("{}", part_2(INPUT));
 }
 
 fn part_1(input: &str) -> u64 {
-    let (mut left, mut right) = parse_input(input);
-    left.sort();
-    right.sort();
-    left.into_iter()
-        .zip(right)
-        .map(|(l, r)| l.abs_diff(r))
-        .sum()
+    let (left, right) = parse_input(input).unwrap();
+    Analysis::new(left, right).distances().into_iter().sum()
 }
 
 fn part_2(input: &str) -> u64 {
-    let (left, right) = parse_input(input);
+    let (left, right) = parse_input(input).unwrap();
     let right_counts =
         right
             .into_iter()
@@ -29,16 +42,91 @@ fn part_2(input: &str) -> u64 {
         .sum()
 }
 
-fn parse_input(input: &str) -> (Vec<u64>, Vec<u64>) {
-    input
-        .lines()
-        .map(|l| l.trim().split_once(" ").unwrap())
-        .map(|(left, right)| {
-            let left: u64 = left.trim().parse().unwrap();
-            let right: u64 = right.trim().parse().unwrap();
-            (left, right)
-        })
-        .unzip()
+fn print_analysis(analysis: &Analysis, bucket_width: u64) {
+    let distances = analysis.distances();
+    println!(
+        "{} pairs, total distance {}",
+        distances.len(),
+        distances.iter().sum::<u64>()
+    );
+    if let Some(median) = analysis.median() {
+        println!("median distance {median}");
+    }
+    for q in QUANTILES {
+        if let Some(distance) = analysis.quantile(q) {
+            println!("  {:>3.0}% of distances <= {distance}", q * 100.0);
+        }
+    }
+    let (only_left, only_right) = analysis.unmatched();
+    println!("{only_left} left value(s) never appear on the right");
+    println!("{only_right} right value(s) never appear on the left");
+    println!("distances in buckets of {bucket_width}:");
+    for (start, count) in analysis.histogram(bucket_width) {
+        println!("  {start:>8}..{:<8} {count}", start + bucket_width);
+    }
+}
+
+/// Both location lists, each sorted so the n-th smallest values are paired up.
+struct Analysis {
+    left: Vec<u64>,
+    right: Vec<u64>,
+}
+
+impl Analysis {
+    fn new(mut left: Vec<u64>, mut right: Vec<u64>) -> Self {
+        left.sort();
+        right.sort();
+        Self { left, right }
+    }
+
+    fn distances(&self) -> Vec<u64> {
+        self.left
+            .iter()
+            .zip(&self.right)
+            .map(|(l, r)| l.abs_diff(*r))
+            .collect()
+    }
+
+    /// The smallest distance that at least a fraction `q` of all distances are at most, or
+    /// `None` without any pairs. The median of an even number of distances is the lower one.
+    fn quantile(&self, q: f64) -> Option<u64> {
+        let mut distances = self.distances();
+        distances.sort();
+        let rank = (q.clamp(0.0, 1.0) * distances.len() as f64).ceil() as usize;
+        distances.get(rank.saturating_sub(1)).copied()
+    }
+
+    fn median(&self) -> Option<u64> {
+        self.quantile(0.5)
+    }
+
+    /// How many distances fall in each bucket, keyed by the smallest distance the bucket holds.
+    fn histogram(&self, bucket_width: u64) -> BTreeMap<u64, usize> {
+        assert!(bucket_width > 0, "Buckets can't be empty!");
+        let mut histogram = BTreeMap::new();
+        for distance in self.distances() {
+            *histogram
+                .entry(distance / bucket_width * bucket_width)
+                .or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// How many entries of the left and of the right list have a value that never appears in
+    /// the other list. Repeated values count once per entry.
+    fn unmatched(&self) -> (usize, usize) {
+        let left: HashSet<_> = self.left.iter().collect();
+        let right: HashSet<_> = self.right.iter().collect();
+        (
+            self.left.iter().filter(|l| !right.contains(l)).count(),
+            self.right.iter().filter(|r| !left.contains(r)).count(),
+        )
+    }
+}
+
+fn parse_input(input: &str) -> Result<(Vec<u64>, Vec<u64>), ParseError> {
+    let [left, right] = parse_columns(input)?;
+    Ok((left, right))
 }
 
 #[cfg(test)]
@@ -54,6 +142,38 @@ mod test {
     fn test_part_2() {
         assert_eq!(part_2(INPUT), 23177084)
     }
+
+    #[test]
+    fn test_analysis() {
+        let (left, right) = parse_input(EXAMPLE).unwrap();
+        let analysis = Analysis::new(left, right);
+        assert_eq!(analysis.distances(), vec![2, 1, 0, 1, 2, 5]);
+        assert_eq!(analysis.median(), Some(1));
+        assert_eq!(analysis.quantile(0.0), Some(0));
+        assert_eq!(analysis.quantile(0.9), Some(5));
+        assert_eq!(
+            analysis.histogram(2),
+            BTreeMap::from([(0, 3), (2, 2), (4, 1)])
+        );
+        assert_eq!(analysis.unmatched(), (2, 2));
+        assert_eq!(Analysis::new(vec![], vec![]).median(), None);
+
+        assert_eq!(
+            parse_input("3   4\n4   3   1"),
+            Err(ParseError::Columns {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    const EXAMPLE: &str = r#"3   4
+4   3
+2   5
+1   3
+3   9
+3   3"#;
 }
 
 #[allow(clippy::items_after_test_module)]
@@ -1,53 +1,190 @@
+use adventofcode_2024::{Args, parse_rows};
+use std::cmp::Reverse;
+
 fn main() {
+    let args = Args::from_env();
+    let tolerance = args
+        .value("--tolerance")
+        .map_or(1, |tolerance| tolerance.parse().unwrap());
+    let default = SafetyPolicy::default();
+    let policy = SafetyPolicy {
+        min_step: args
+            .value("--min-step")
+            .map_or(default.min_step, |step| step.parse().unwrap()),
+        max_step: args
+            .value("--max-step")
+            .map_or(default.max_step, |step| step.parse().unwrap()),
+        allow_plateaus: args.flag("--plateaus"),
+        direction: if args.flag("--either-direction") {
+            Direction::Either
+        } else {
+            Direction::Required
+        },
+    };
+    if args.flag("--unsafe") {
+        for (line, report) in parse_input(INPUT).iter().enumerate() {
+            if let Err(violation) = report.check(&policy) {
+                let fix = match report.dampen(tolerance, &policy) {
+                    Some(drops) => format!("drop positions {drops:?}"),
+                    None => format!("can't be fixed by dropping {tolerance} level(s)"),
+                };
+                println!("line {}: {report}: {violation}; {fix}", line + 1);
+            }
+        }
+    }
     println!("{}", part_1(INPUT));
     println!("{}", part_2(INPUT));
+    if policy != SafetyPolicy::default() || tolerance != 1 {
+        println!("{}", count_safe(INPUT, &policy));
+        println!("{}", count_dampened(INPUT, tolerance, &policy));
+    }
 }
 
 fn part_1(input: &str) -> usize {
+    count_safe(input, &SafetyPolicy::default())
+}
+
+fn part_2(input: &str) -> usize {
+    count_dampened(input, 1, &SafetyPolicy::default())
+}
+
+fn count_safe(input: &str, policy: &SafetyPolicy) -> usize {
     parse_input(input)
         .into_iter()
-        .filter(Report::is_safe)
+        .filter(|report| report.is_safe(policy))
         .count()
 }
 
-fn part_2(input: &str) -> usize {
+fn count_dampened(input: &str, tolerance: usize, policy: &SafetyPolicy) -> usize {
     parse_input(input)
         .into_iter()
-        .filter(|report| (0..report.len()).any(|idx| report.tolerate(idx).is_safe()))
+        .filter(|report| report.dampen(tolerance, policy).is_some())
         .count()
 }
 
+/// The rules a report has to follow to be safe. The default is the puzzle's: levels change by
+/// 1 to 3 at a time and all in the same direction.
+#[derive(Debug, PartialEq)]
+struct SafetyPolicy {
+    min_step: u64,
+    max_step: u64,
+    /// Equal neighbours are only allowed with this, whatever `min_step` is.
+    allow_plateaus: bool,
+    direction: Direction,
+}
+
+#[derive(Debug, PartialEq)]
+enum Direction {
+    /// All levels increase or all decrease, ignoring plateaus.
+    Required,
+    Either,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self {
+            min_step: 1,
+            max_step: 3,
+            allow_plateaus: false,
+            direction: Direction::Required,
+        }
+    }
+}
+
 fn parse_input(input: &str) -> Vec<Report> {
-    input
-        .lines()
-        .map(|l| {
-            Report(
-                l.split_whitespace()
-                    .map(|level| level.parse().unwrap())
-                    .collect(),
-            )
-        })
+    parse_rows(input)
+        .map(|levels| Report(levels.unwrap_or_else(|error| panic!("{error}"))))
         .collect()
 }
 
 struct Report(Vec<u64>);
 
 impl Report {
-    fn is_safe(&self) -> bool {
+    fn is_safe(&self, policy: &SafetyPolicy) -> bool {
+        self.check(policy).is_ok()
+    }
+
+    /// Finds the first pair of levels that breaks the rules.
+    fn check(&self, policy: &SafetyPolicy) -> Result<(), Unsafe> {
         self.0
             .iter()
-            .try_fold(Trend::Empty, |trend, next| trend.step(next))
-            .is_ok()
+            .enumerate()
+            .try_fold(Trend::Empty, |trend, (idx, next)| {
+                trend.step(idx, next, policy)
+            })
+            .map(|_| ())
     }
 
-    fn tolerate(&self, idx: usize) -> Self {
-        let mut levels = self.0.clone();
-        levels.remove(idx);
-        Self(levels)
+    /// The positions of the fewest levels, at most `tolerance`, whose removal makes the report
+    /// safe, or `None` if that takes more.
+    fn dampen(&self, tolerance: usize, policy: &SafetyPolicy) -> Option<Vec<usize>> {
+        [Trend::Up as fn(u64) -> Trend, Trend::Down]
+            .into_iter()
+            .filter_map(|trend| self.dampen_towards(trend, tolerance, policy))
+            .min_by_key(Vec::len)
     }
 
-    fn len(&self) -> usize {
-        self.0.len()
+    /// Keeps a level only if it can follow the last kept one in the direction `trend` is built
+    /// with. Between two kept levels at most `tolerance` are dropped, so each level only looks
+    /// back `tolerance + 1` places and the search is linear in the length of the report.
+    fn dampen_towards(
+        &self,
+        trend: fn(u64) -> Trend,
+        tolerance: usize,
+        policy: &SafetyPolicy,
+    ) -> Option<Vec<usize>> {
+        let levels = &self.0;
+        if levels.is_empty() {
+            return Some(Vec::new());
+        }
+        // For each level kept as the latest one, the fewest drops so far and the kept level
+        // before it. Ties keep the later level, so earlier ones are dropped like in the puzzle.
+        let mut best: Vec<Option<(usize, Option<usize>)>> = Vec::with_capacity(levels.len());
+        for (idx, level) in levels.iter().enumerate() {
+            let first = (idx <= tolerance).then_some((idx, None));
+            let after = (idx.saturating_sub(tolerance + 1)..idx).filter_map(|prev| {
+                let (n_dropped, _) = best[prev]?;
+                let n_dropped = n_dropped + idx - prev - 1;
+                let follows = trend(levels[prev]).step(idx, level, policy).is_ok();
+                (follows && n_dropped <= tolerance).then_some((n_dropped, Some(prev)))
+            });
+            best.push(
+                first
+                    .into_iter()
+                    .chain(after)
+                    .min_by_key(|&(n_dropped, prev)| (n_dropped, Reverse(prev))),
+            );
+        }
+
+        let (mut last, _) = best
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, best)| {
+                best.map(|(n_dropped, _)| (idx, n_dropped + levels.len() - 1 - idx))
+            })
+            .filter(|&(_, n_dropped)| n_dropped <= tolerance)
+            .min_by_key(|&(_, n_dropped)| n_dropped)?;
+        let mut kept = vec![false; levels.len()];
+        loop {
+            kept[last] = true;
+            match best[last] {
+                Some((_, Some(prev))) => last = prev,
+                _ => break,
+            }
+        }
+        Some((0..levels.len()).filter(|&idx| !kept[idx]).collect())
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let levels = self
+            .0
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{levels}")
     }
 }
 
@@ -59,40 +196,81 @@ enum Trend {
 }
 
 impl Trend {
-    fn step(self, next: &u64) -> Result<Self, Unsafe> {
+    /// `idx` is the position of `next` in the report, used to point at the offending level.
+    fn step(self, idx: usize, next: &u64, policy: &SafetyPolicy) -> Result<Self, Unsafe> {
         let next = *next;
-        match self {
-            Self::Empty => Ok(Self::None(next)),
-            Self::None(current) => {
-                if (1..=3).contains(&current.abs_diff(next)) {
-                    if current < next {
-                        Ok(Self::Up(next))
-                    } else {
-                        Ok(Self::Down(next))
-                    }
-                } else {
-                    Err(Unsafe)
-                }
-            }
-            Self::Up(current) => {
-                if current < next && next <= current + 3 {
-                    Ok(Self::Up(next))
-                } else {
-                    Err(Unsafe)
-                }
-            }
-            Self::Down(current) => {
-                if current.saturating_sub(3) <= next && next < current {
-                    Ok(Self::Down(next))
-                } else {
-                    Err(Unsafe)
-                }
-            }
+        let current = match self {
+            Self::Empty => return Ok(Self::None(next)),
+            Self::None(current) | Self::Up(current) | Self::Down(current) => current,
+        };
+        let unsafe_because = |reason| {
+            Err(Unsafe {
+                idx,
+                levels: (current, next),
+                reason,
+            })
+        };
+        if current == next {
+            // Equal levels keep the trend as it is.
+            return if policy.allow_plateaus {
+                Ok(self)
+            } else {
+                unsafe_because(Reason::Equal)
+            };
+        }
+        let turned = match self {
+            Self::Up(_) => next < current,
+            Self::Down(_) => next > current,
+            _ => false,
+        };
+        let step = current.abs_diff(next);
+        if turned && policy.direction == Direction::Required {
+            unsafe_because(Reason::DirectionChange)
+        } else if step > policy.max_step {
+            unsafe_because(Reason::StepTooLarge)
+        } else if step < policy.min_step {
+            unsafe_because(Reason::StepTooSmall)
+        } else if current < next {
+            Ok(Self::Up(next))
+        } else {
+            Ok(Self::Down(next))
         }
     }
 }
 
-struct Unsafe;
+/// The first violation in a report: the level at `idx` and the one before it, as `levels`.
+#[derive(Debug, PartialEq)]
+struct Unsafe {
+    idx: usize,
+    levels: (u64, u64),
+    reason: Reason,
+}
+
+#[derive(Debug, PartialEq)]
+enum Reason {
+    DirectionChange,
+    StepTooLarge,
+    StepTooSmall,
+    Equal,
+}
+
+impl std::fmt::Display for Unsafe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (current, next) = self.levels;
+        let reason = match self.reason {
+            Reason::DirectionChange => "change direction",
+            Reason::StepTooLarge => "differ by too much",
+            Reason::StepTooSmall => "differ by too little",
+            Reason::Equal => "are equal",
+        };
+        write!(
+            f,
+            "levels {current} and {next} (positions {} and {}) {reason}",
+            self.idx - 1,
+            self.idx
+        )
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -107,6 +285,133 @@ mod test {
     fn test_part_2() {
         assert_eq!(part_2(INPUT), 418)
     }
+
+    #[test]
+    fn test_check() {
+        let policy = SafetyPolicy::default();
+        let reports = parse_input(EXAMPLE);
+        let violation = |idx, levels, reason| {
+            Err(Unsafe {
+                idx,
+                levels,
+                reason,
+            })
+        };
+        assert_eq!(reports[0].check(&policy), Ok(()));
+        assert_eq!(
+            reports[1].check(&policy),
+            violation(2, (2, 7), Reason::StepTooLarge)
+        );
+        assert_eq!(
+            reports[3].check(&policy),
+            violation(2, (3, 2), Reason::DirectionChange)
+        );
+        assert_eq!(
+            reports[4].check(&policy),
+            violation(3, (4, 4), Reason::Equal)
+        );
+    }
+
+    #[test]
+    fn test_dampen() {
+        let policy = SafetyPolicy::default();
+        let reports = parse_input(EXAMPLE);
+        let drops: Vec<_> = reports
+            .iter()
+            .map(|report| report.dampen(1, &policy))
+            .collect();
+        assert_eq!(
+            drops,
+            vec![
+                Some(vec![]),
+                None,
+                None,
+                Some(vec![1]),
+                Some(vec![2]),
+                Some(vec![])
+            ]
+        );
+        assert_eq!(
+            Report(vec![1, 5, 9, 2, 3]).dampen(2, &policy),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            Report(vec![9, 1, 2, 8, 3]).dampen(2, &policy),
+            Some(vec![0, 3])
+        );
+        assert_eq!(Report(vec![7, 7]).dampen(1, &policy), Some(vec![1]));
+        assert_eq!(Report(vec![1, 2]).dampen(2, &policy), Some(vec![]));
+        assert_eq!(Report(vec![1, 2, 3]).dampen(3, &policy), Some(vec![]));
+        assert_eq!(Report(vec![5, 5, 5]).dampen(3, &policy), Some(vec![1, 2]));
+        assert_eq!(Report(vec![]).dampen(0, &policy), Some(vec![]));
+        assert_eq!(Report(vec![1, 9, 20]).dampen(1, &policy), None);
+
+        // Agrees with trying every single removal.
+        for report in parse_input(INPUT) {
+            let brute_force = (0..report.0.len()).any(|idx| {
+                let mut levels = report.0.clone();
+                levels.remove(idx);
+                Report(levels).is_safe(&policy)
+            });
+            assert_eq!(report.dampen(1, &policy).is_some(), brute_force, "{report}");
+        }
+    }
+
+    #[test]
+    fn test_policies() {
+        let puzzle = SafetyPolicy::default();
+        // Steps down to zero are measured exactly, not clamped.
+        assert!(Report(vec![3, 0]).is_safe(&puzzle));
+        assert!(Report(vec![5, 2, 0]).is_safe(&puzzle));
+        assert_eq!(
+            Report(vec![4, 0]).check(&puzzle),
+            Err(Unsafe {
+                idx: 1,
+                levels: (4, 0),
+                reason: Reason::StepTooLarge
+            })
+        );
+
+        let wide = SafetyPolicy {
+            min_step: 2,
+            max_step: 5,
+            ..SafetyPolicy::default()
+        };
+        assert!(Report(vec![9, 4, 2]).is_safe(&wide));
+        assert_eq!(
+            Report(vec![9, 4, 3]).check(&wide),
+            Err(Unsafe {
+                idx: 2,
+                levels: (4, 3),
+                reason: Reason::StepTooSmall
+            })
+        );
+
+        let plateaus = SafetyPolicy {
+            allow_plateaus: true,
+            ..SafetyPolicy::default()
+        };
+        assert!(Report(vec![1, 1, 2, 2, 4]).is_safe(&plateaus));
+        assert!(!Report(vec![1, 2, 2, 1]).is_safe(&plateaus));
+
+        let either = SafetyPolicy {
+            direction: Direction::Either,
+            ..SafetyPolicy::default()
+        };
+        assert!(Report(vec![1, 3, 2, 4, 1]).is_safe(&either));
+        assert!(!Report(vec![1, 3, 3, 4]).is_safe(&either));
+        assert_eq!(
+            Report(vec![1, 3, 7, 4, 2]).dampen(1, &either),
+            Some(vec![2])
+        );
+    }
+
+    const EXAMPLE: &str = r#"7 6 4 2 1
+1 2 7 8 9
+9 7 6 2 1
+1 3 2 4 5
+8 6 4 4 1
+1 3 6 7 9"#;
 }
 
 #[allow(clippy::items_after_test_module)]
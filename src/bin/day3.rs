@@ -1,67 +1,462 @@
+use adventofcode_2024::Args;
+use std::io::{ErrorKind, Read};
+
 fn main() {
-    println!("{}", part_1(INPUT));
-    println!("{}", part_2(INPUT));
+    let args = Args::from_env();
+    let interpreter = Interpreter::new().register(MUL).register(DO).register(DONT);
+    if args.flag("--annotate") {
+        match args.value("--file") {
+            Some(path) => interpreter.annotate(std::fs::File::open(path).unwrap()),
+            None => interpreter.annotate(INPUT.as_bytes()),
+        }
+        .unwrap();
+    } else if let Some(path) = args.value("--file") {
+        let machine = interpreter
+            .run_reader(std::fs::File::open(path).unwrap())
+            .unwrap();
+        println!("{}", machine.total);
+    } else {
+        println!("{}", part_1(INPUT));
+        println!("{}", part_2(INPUT));
+    }
 }
 
-fn part_1(input: &str) -> u64 {
-    input
-        .split("mul(")
-        .filter_map(|after_mul_open| {
-            AfterMulOpen(after_mul_open)
-                .close()
-                .and_then(MulContents::compute)
-        })
-        .sum()
+fn part_1(input: &str) -> i64 {
+    Interpreter::new().register(MUL).run(input).total
 }
 
-fn part_2(input: &str) -> u64 {
-    remove_disabled(input)
-        .split("mul(")
-        .filter_map(|after_mul_open| {
-            AfterMulOpen(after_mul_open)
-                .close()
-                .and_then(MulContents::compute)
-        })
-        .sum()
+fn part_2(input: &str) -> i64 {
+    Interpreter::new()
+        .register(MUL)
+        .register(DO)
+        .register(DONT)
+        .run(input)
+        .total
 }
 
-struct AfterMulOpen<'a>(&'a str);
+const MAX_DIGITS: u8 = 3;
+const BUFFER_SIZE: usize = 64 * 1024;
+
+const MUL: Instruction = Instruction {
+    name: "mul",
+    arity: 2,
+    gated: true,
+    run: |machine, args| machine.total += args[0] * args[1],
+};
+
+const DO: Instruction = Instruction {
+    name: "do",
+    arity: 0,
+    gated: false,
+    run: |machine, _| machine.enabled = true,
+};
+
+const DONT: Instruction = Instruction {
+    name: "don't",
+    arity: 0,
+    gated: false,
+    run: |machine, _| machine.enabled = false,
+};
+
+/// An instruction written as `name(a,b,...)` with exactly `arity` operands of 1-3 digits each.
+/// Gated instructions are skipped while the machine is disabled.
+#[derive(Clone, Copy)]
+struct Instruction {
+    name: &'static str,
+    arity: usize,
+    gated: bool,
+    run: fn(&mut Machine, &[i64]),
+}
+
+#[derive(Debug)]
+struct Machine {
+    enabled: bool,
+    total: i64,
+}
+
+/// Runs the instructions registered with it, ignoring anything else in the corrupted memory.
+struct Interpreter {
+    instructions: Vec<Instruction>,
+}
+
+impl Interpreter {
+    fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+        }
+    }
+
+    fn register(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    fn lexer(&self) -> Lexer {
+        Lexer::new(
+            self.instructions
+                .iter()
+                .map(|instruction| (instruction.name, instruction.arity)),
+        )
+    }
+
+    fn run(&self, input: &str) -> Machine {
+        self.run_reader(input.as_bytes()).unwrap()
+    }
+
+    fn run_reader(&self, reader: impl Read) -> std::io::Result<Machine> {
+        self.scan(reader, |_, _| {})
+    }
 
-impl<'a> AfterMulOpen<'a> {
-    fn close(self) -> Option<MulContents<'a>> {
-        self.0
-            .split_once(")")
-            .map(|(contents, _)| MulContents(contents))
+    /// Reads the memory through a fixed size buffer. The lexer keeps any partial instruction
+    /// between reads, so instructions split across buffer boundaries are still recognized, and
+    /// memory use doesn't grow with the input. `observe` sees every lexeme together with the
+    /// machine as it was just before the lexeme is executed.
+    fn scan(
+        &self,
+        mut reader: impl Read,
+        mut observe: impl FnMut(&Lexeme, &Machine),
+    ) -> std::io::Result<Machine> {
+        let mut lexer = self.lexer();
+        let mut machine = Machine {
+            enabled: true,
+            total: 0,
+        };
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            let n_read = match reader.read(&mut buffer) {
+                Ok(0) => {
+                    for lexeme in lexer.finish() {
+                        observe(&lexeme, &machine);
+                    }
+                    return Ok(machine);
+                }
+                Ok(n_read) => n_read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            for lexeme in buffer[..n_read].iter().flat_map(|byte| lexer.feed(*byte)) {
+                observe(&lexeme, &machine);
+                if let Lexeme::Token(spanned) = lexeme {
+                    let instruction = self.instructions[spanned.token.instruction];
+                    if machine.enabled || !instruction.gated {
+                        (instruction.run)(&mut machine, &spanned.token.args);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prints every occurrence of an instruction with its offset and what became of it, then
+    /// the totals.
+    fn annotate(&self, reader: impl Read) -> std::io::Result<Machine> {
+        let (mut n_accepted, mut n_skipped, mut n_rejected) = (0, 0, 0);
+        let machine = self.scan(reader, |lexeme, machine| match lexeme {
+            Lexeme::Token(spanned) => {
+                let instruction = self.instructions[spanned.token.instruction];
+                let args = spanned
+                    .token
+                    .args
+                    .iter()
+                    .map(i64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let text = format!("{}({args})", instruction.name);
+                if instruction.gated && !machine.enabled {
+                    n_skipped += 1;
+                    println!("{:>8} {text:<16} skipped (disabled)", spanned.offset);
+                } else {
+                    n_accepted += 1;
+                    println!("{:>8} {text:<16} accepted", spanned.offset);
+                }
+            }
+            Lexeme::Rejected(rejected) => {
+                n_rejected += 1;
+                println!(
+                    "{:>8} {:<16} rejected: {}",
+                    rejected.offset, rejected.text, rejected.reason
+                );
+            }
+        })?;
+        println!(
+            "{n_accepted} accepted, {n_skipped} skipped, {n_rejected} rejected, total {}",
+            machine.total
+        );
+        Ok(machine)
     }
 }
 
-struct MulContents<'a>(&'a str);
+/// A call to the instruction at index `instruction` of the lexer's table.
+#[derive(Debug, PartialEq)]
+struct Token {
+    instruction: usize,
+    args: Vec<i64>,
+}
 
-impl MulContents<'_> {
-    fn compute(self) -> Option<u64> {
-        self.0.split_once(",").and_then(|(first, second)| {
-            if let (Ok(first), Ok(second)) = (first.parse::<u64>(), second.parse::<u64>()) {
-                Some(first * second)
-            } else {
-                None
+/// A token with the byte offset of its first character.
+#[derive(Debug, PartialEq)]
+struct Spanned {
+    offset: usize,
+    token: Token,
+}
+
+#[derive(Debug, PartialEq)]
+enum Lexeme {
+    Token(Spanned),
+    Rejected(Rejected),
+}
+
+/// An instruction name that didn't turn into a valid call. `text` is everything matched from
+/// the name up to and including the byte that broke it, escaped for printing.
+#[derive(Debug, PartialEq)]
+struct Rejected {
+    offset: usize,
+    instruction: usize,
+    text: String,
+    reason: Reason,
+}
+
+/// `found` is `None` when the input ended first.
+#[derive(Debug, PartialEq)]
+enum Reason {
+    BadDigits { found: Option<u8> },
+    TooManyDigits,
+    MissingParen { expected: u8, found: Option<u8> },
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let describe = |found: &Option<u8>| match found {
+            Some(byte) => format!("`{}`", byte.escape_ascii()),
+            None => "end of input".to_string(),
+        };
+        match self {
+            Self::BadDigits { found } => {
+                write!(f, "bad digits, unexpected {}", describe(found))
             }
-        })
+            Self::TooManyDigits => write!(f, "bad digits, more than {MAX_DIGITS} digits"),
+            Self::MissingParen { expected, found } => write!(
+                f,
+                "missing paren, expected `{}` but found {}",
+                *expected as char,
+                describe(found)
+            ),
+        }
     }
 }
 
-fn remove_disabled(input: &str) -> String {
-    format!("do(){}", input)
-        .split("do()")
-        .skip(1)
-        .map(|after_do| {
-            if let Some((before_dont, _)) = after_do.split_once("don't()") {
-                before_dont
-            } else {
-                after_do
+/// Recognizes instructions one byte at a time, so it never needs to look back at input it has
+/// already been fed beyond `text`, the partial instruction matched so far.
+struct Lexer {
+    keywords: Vec<Vec<u8>>,
+    arities: Vec<usize>,
+    state: State,
+    text: Vec<u8>,
+    start: usize,
+    offset: usize,
+}
+
+enum State {
+    Keyword,
+    Operands {
+        instruction: usize,
+        args: Vec<i64>,
+        operand: Operand,
+    },
+}
+
+#[derive(Clone, Copy, Default)]
+struct Operand {
+    value: i64,
+    digits: u8,
+}
+
+impl Lexer {
+    /// Names can't contain `(`, so no keyword is a prefix of another.
+    fn new<'a>(instructions: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let (keywords, arities): (Vec<_>, Vec<_>) = instructions
+            .into_iter()
+            .map(|(name, arity)| {
+                assert!(
+                    !name.is_empty() && !name.contains('('),
+                    "Instruction names must be non-empty and can't contain `(`!"
+                );
+                (format!("{name}(").into_bytes(), arity)
+            })
+            .unzip();
+        Self {
+            keywords,
+            arities,
+            state: State::Keyword,
+            text: Vec::new(),
+            start: 0,
+            offset: 0,
+        }
+    }
+
+    /// Usually yields nothing, but a byte that breaks a partial instruction can yield both its
+    /// rejection and whatever the rest of it turns out to hold.
+    fn feed(&mut self, byte: u8) -> Vec<Lexeme> {
+        let mut lexemes = Vec::new();
+        let offset = self.offset;
+        self.offset += 1;
+        self.step(byte, offset, &mut lexemes);
+        lexemes
+    }
+
+    /// Reports the instructions left unfinished at the end of the input.
+    fn finish(&mut self) -> Vec<Lexeme> {
+        let mut lexemes = Vec::new();
+        while !self.text.is_empty() {
+            let unfinished = match &self.state {
+                State::Keyword => self.named(&self.text).map(|instruction| {
+                    (
+                        instruction,
+                        Reason::MissingParen {
+                            expected: b'(',
+                            found: None,
+                        },
+                    )
+                }),
+                State::Operands {
+                    instruction,
+                    args,
+                    operand,
+                } => Some((*instruction, self.reason(*instruction, args, operand, None))),
+            };
+            if let Some((instruction, reason)) = unfinished {
+                lexemes.push(self.reject(instruction, reason));
             }
+            self.restart(&mut lexemes);
+        }
+        lexemes
+    }
+
+    fn step(&mut self, byte: u8, offset: usize, lexemes: &mut Vec<Lexeme>) {
+        if self.text.is_empty() {
+            self.start = offset;
+        }
+        self.text.push(byte);
+        match std::mem::replace(&mut self.state, State::Keyword) {
+            State::Keyword => match self.keywords.iter().position(|k| k.starts_with(&self.text)) {
+                Some(instruction) if self.keywords[instruction].len() == self.text.len() => {
+                    self.state = State::Operands {
+                        instruction,
+                        args: Vec::with_capacity(self.arities[instruction]),
+                        operand: Operand::default(),
+                    };
+                }
+                Some(_) => {}
+                None => {
+                    if let Some(instruction) = self.named(&self.text[..self.text.len() - 1]) {
+                        lexemes.push(self.reject(
+                            instruction,
+                            Reason::MissingParen {
+                                expected: b'(',
+                                found: Some(byte),
+                            },
+                        ));
+                    }
+                    self.restart(lexemes);
+                }
+            },
+            State::Operands {
+                instruction,
+                mut args,
+                operand,
+            } => {
+                let arity = self.arities[instruction];
+                let n_operands = args.len() + usize::from(operand.digits > 0);
+                if byte.is_ascii_digit() && args.len() < arity && operand.digits < MAX_DIGITS {
+                    self.state = State::Operands {
+                        instruction,
+                        args,
+                        operand: Operand {
+                            value: operand.value * 10 + i64::from(byte - b'0'),
+                            digits: operand.digits + 1,
+                        },
+                    };
+                } else if byte == b',' && operand.digits > 0 && n_operands < arity {
+                    args.push(operand.value);
+                    self.state = State::Operands {
+                        instruction,
+                        args,
+                        operand: Operand::default(),
+                    };
+                } else if byte == b')' && n_operands == arity && (arity == 0 || operand.digits > 0)
+                {
+                    if operand.digits > 0 {
+                        args.push(operand.value);
+                    }
+                    self.text.clear();
+                    lexemes.push(Lexeme::Token(Spanned {
+                        offset: self.start,
+                        token: Token { instruction, args },
+                    }));
+                } else {
+                    let reason = self.reason(instruction, &args, &operand, Some(byte));
+                    lexemes.push(self.reject(instruction, reason));
+                    self.restart(lexemes);
+                }
+            }
+        }
+    }
+
+    /// Why `found` can't continue the operands read so far.
+    fn reason(
+        &self,
+        instruction: usize,
+        args: &[i64],
+        operand: &Operand,
+        found: Option<u8>,
+    ) -> Reason {
+        let arity = self.arities[instruction];
+        let n_operands = args.len() + usize::from(operand.digits > 0);
+        if found.is_some_and(|byte| byte.is_ascii_digit()) && args.len() < arity {
+            Reason::TooManyDigits
+        } else if n_operands == arity && (arity == 0 || operand.digits > 0) {
+            Reason::MissingParen {
+                expected: b')',
+                found,
+            }
+        } else {
+            Reason::BadDigits { found }
+        }
+    }
+
+    fn reject(&self, instruction: usize, reason: Reason) -> Lexeme {
+        Lexeme::Rejected(Rejected {
+            offset: self.start,
+            instruction,
+            text: self
+                .text
+                .iter()
+                .map(|&byte| match byte {
+                    b' '..=b'~' => (byte as char).to_string(),
+                    _ => byte.escape_ascii().to_string(),
+                })
+                .collect(),
+            reason,
         })
-        .collect::<Vec<_>>()
-        .join("")
+    }
+
+    /// The instruction whose name is exactly `text`.
+    fn named(&self, text: &[u8]) -> Option<usize> {
+        self.keywords
+            .iter()
+            .position(|keyword| &keyword[..keyword.len() - 1] == text)
+    }
+
+    /// Drops a partial instruction and scans everything after its first byte again, since any
+    /// of it may start the next instruction, like `mul(` in `mumul(` or `aab(` in `aaab(`.
+    fn restart(&mut self, lexemes: &mut Vec<Lexeme>) {
+        let start = self.start;
+        let rest = self.text.split_off(1);
+        self.state = State::Keyword;
+        self.text.clear();
+        for (idx, byte) in rest.into_iter().enumerate() {
+            self.step(byte, start + 1 + idx, lexemes);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +472,192 @@ mod test {
     fn test_part_2() {
         assert_eq!(part_2(INPUT), 93729253)
     }
+
+    #[test]
+    fn test_lexer() {
+        let interpreter = Interpreter::new().register(MUL).register(DO).register(DONT);
+        let tokenize = |input: &str| {
+            let mut lexer = interpreter.lexer();
+            input
+                .bytes()
+                .flat_map(|byte| lexer.feed(byte))
+                .filter_map(|lexeme| match lexeme {
+                    Lexeme::Token(spanned) => Some((
+                        spanned.offset,
+                        spanned.token.instruction,
+                        spanned.token.args,
+                    )),
+                    Lexeme::Rejected(_) => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            tokenize("xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))"),
+            vec![
+                (1, 0, vec![2, 4]),
+                (20, 2, vec![]),
+                (28, 0, vec![5, 5]),
+                (48, 0, vec![11, 8]),
+                (59, 1, vec![]),
+                (64, 0, vec![8, 5]),
+            ]
+        );
+        assert_eq!(
+            tokenize("mul(+2,3) mul( 2,3) mul(1234,5) mul(1,) mul(,1) mul(2,3 do(1) mumul(4,5)"),
+            vec![(64, 0, vec![4, 5])]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_names() {
+        const AAB: Instruction = Instruction {
+            name: "aab",
+            arity: 0,
+            gated: false,
+            run: |machine, _| machine.total += 1,
+        };
+        const LMAO: Instruction = Instruction {
+            name: "lmao",
+            arity: 0,
+            gated: false,
+            run: |machine, _| machine.total += 10,
+        };
+        const UL: Instruction = Instruction {
+            name: "ul",
+            arity: 1,
+            gated: false,
+            run: |machine, args| machine.total += args[0],
+        };
+
+        let interpreter = Interpreter::new()
+            .register(AAB)
+            .register(MUL)
+            .register(LMAO)
+            .register(UL);
+        let mut lexer = interpreter.lexer();
+        let mut lexemes = "aaab() aaaab() mulmao() mumul(2,3) mul(7) aaa"
+            .bytes()
+            .flat_map(|byte| lexer.feed(byte))
+            .collect::<Vec<_>>();
+        lexemes.extend(lexer.finish());
+        let tokens = lexemes
+            .into_iter()
+            .filter_map(|lexeme| match lexeme {
+                Lexeme::Token(spanned) => Some((spanned.offset, spanned.token.instruction)),
+                Lexeme::Rejected(_) => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, vec![(1, 0), (9, 0), (17, 2), (26, 1), (36, 3)]);
+
+        let machine = interpreter.run("aaab() aaaab() mulmao() mumul(2,3) mul(7) aaa");
+        assert_eq!(machine.total, 1 + 1 + 10 + 6 + 7);
+    }
+
+    #[test]
+    fn test_rejections() {
+        let interpreter = Interpreter::new().register(MUL).register(DO).register(DONT);
+        let mut lexer = interpreter.lexer();
+        let mut lexemes = "mul(2,3]mul(1234,5)mul[1,2]do(1)mul(+2,3)mul(2 3)don't"
+            .bytes()
+            .flat_map(|byte| lexer.feed(byte))
+            .collect::<Vec<_>>();
+        lexemes.extend(lexer.finish());
+        let mut rejections = lexemes.into_iter().filter_map(|lexeme| match lexeme {
+            Lexeme::Token(_) => None,
+            Lexeme::Rejected(rejected) => Some((rejected.offset, rejected.text, rejected.reason)),
+        });
+
+        let missing = |expected, found| Reason::MissingParen { expected, found };
+        let bad = |found| Reason::BadDigits { found };
+        assert_eq!(
+            rejections.next(),
+            Some((0, "mul(2,3]".to_string(), missing(b')', Some(b']'))))
+        );
+        assert_eq!(
+            rejections.next(),
+            Some((8, "mul(1234".to_string(), Reason::TooManyDigits))
+        );
+        assert_eq!(
+            rejections.next(),
+            Some((19, "mul[".to_string(), missing(b'(', Some(b'['))))
+        );
+        assert_eq!(
+            rejections.next(),
+            Some((27, "do(1".to_string(), missing(b')', Some(b'1'))))
+        );
+        assert_eq!(
+            rejections.next(),
+            Some((32, "mul(+".to_string(), bad(Some(b'+'))))
+        );
+        assert_eq!(
+            rejections.next(),
+            Some((41, "mul(2 ".to_string(), bad(Some(b' '))))
+        );
+        assert_eq!(
+            rejections.next(),
+            Some((49, "don't".to_string(), missing(b'(', None)))
+        );
+        assert_eq!(rejections.next(), None);
+    }
+
+    #[test]
+    fn test_run_reader_across_boundaries() {
+        /// Hands out at most `chunk_size` bytes per read, splitting most instructions.
+        struct Trickle<'a> {
+            data: &'a [u8],
+            chunk_size: usize,
+        }
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.chunk_size.min(buf.len()).min(self.data.len());
+                buf[..n].copy_from_slice(&self.data[..n]);
+                self.data = &self.data[n..];
+                Ok(n)
+            }
+        }
+
+        let interpreter = Interpreter::new().register(MUL).register(DO).register(DONT);
+        for chunk_size in [1, 2, 3, 7] {
+            let reader = Trickle {
+                data: INPUT.as_bytes(),
+                chunk_size,
+            };
+            assert_eq!(interpreter.run_reader(reader).unwrap().total, 93729253);
+        }
+    }
+
+    #[test]
+    fn test_custom_instructions() {
+        const ADD: Instruction = Instruction {
+            name: "add",
+            arity: 2,
+            gated: true,
+            run: |machine, args| machine.total += args[0] + args[1],
+        };
+        const SUB: Instruction = Instruction {
+            name: "sub",
+            arity: 2,
+            gated: true,
+            run: |machine, args| machine.total -= args[0] - args[1],
+        };
+        const TOGGLE: Instruction = Instruction {
+            name: "toggle",
+            arity: 0,
+            gated: false,
+            run: |machine, _| machine.enabled = !machine.enabled,
+        };
+
+        let interpreter = Interpreter::new()
+            .register(MUL)
+            .register(ADD)
+            .register(SUB)
+            .register(TOGGLE);
+        let machine = interpreter.run("add(1,2)sub(30,3)toggle()mul(2,2)toggle()mul(3,3)xdo()");
+        assert_eq!(machine.total, 3 - 27 + 9);
+        assert!(machine.enabled);
+    }
 }
 
 #[allow(clippy::items_after_test_module)]
@@ -1,61 +1,90 @@
-use adventofcode_2024::Array2D;
+use adventofcode_2024::{Args, Array2D, Direction, Pattern, WordSearch};
+use std::collections::HashMap;
 
 fn main() {
+    let args = Args::from_env();
+    let puzzle = Array2D::from_rows(INPUT.lines().map(str::chars));
+    let color = args.flag("--color");
+    match args.value("--show") {
+        Some("xmas") => println!("{}", render(&puzzle, xmas_cells(&puzzle), color)),
+        Some("x-mas") => println!("{}", render(&puzzle, x_mas_cells(&puzzle), color)),
+        Some(search) => panic!("Unknown search {search}, expected xmas or x-mas!"),
+        None => {}
+    }
     println!("{}", part_1(INPUT));
     println!("{}", part_2(INPUT));
 }
 
 fn part_1(input: &str) -> usize {
     let puzzle = Array2D::from_rows(input.lines().map(str::chars));
-    puzzle.convolve(horizontal_linear_xmas_kernel, (1, 4)).sum()
-        + puzzle.convolve(vertical_linear_xmas_kernel, (4, 1)).sum()
-        + puzzle
-            .convolve(primary_diagonal_linear_xmas_kernel, (4, 4))
-            .sum()
-        + puzzle
-            .convolve(secondary_diagonal_linear_xmas_kernel, (4, 4))
-            .sum()
+    WordSearch::new(Direction::ALL).count(&puzzle, &word("XMAS"))
 }
 
 fn part_2(input: &str) -> usize {
     let puzzle = Array2D::from_rows(input.lines().map(str::chars));
-    puzzle.convolve(cross_xmas_count_kernel, (3, 3)).sum()
+    Pattern::parse(X_MAS)
+        .orientations()
+        .iter()
+        .map(|pattern| pattern.find(&puzzle).count())
+        .sum()
 }
 
-fn horizontal_linear_xmas_kernel(inp: Array2D<&char>) -> usize {
-    let inp: String = inp[0].iter().copied().collect();
-    if inp == "XMAS" || inp == "SAMX" { 1 } else { 0 }
-}
+const X_MAS: &str = "M.S / .A. / M.S";
 
-fn vertical_linear_xmas_kernel(inp: Array2D<&char>) -> usize {
-    let inp: String = inp.cols().next().unwrap().into_iter().copied().collect();
-    if inp == "XMAS" || inp == "SAMX" { 1 } else { 0 }
+fn word(word: &str) -> Vec<char> {
+    word.chars().collect()
 }
 
-fn primary_diagonal_linear_xmas_kernel(inp: Array2D<&char>) -> usize {
-    let inp: String = vec![inp[3][0], inp[2][1], inp[1][2], inp[0][3]]
-        .into_iter()
-        .collect();
-    if inp == "XMAS" || inp == "SAMX" { 1 } else { 0 }
+/// Every cell of every `XMAS`, once per match it's part of.
+fn xmas_cells(puzzle: &Array2D<char>) -> Vec<(usize, usize)> {
+    WordSearch::new(Direction::ALL)
+        .find(puzzle, &word("XMAS"))
+        .flat_map(|xmas| xmas.cells())
+        .collect()
 }
 
-fn secondary_diagonal_linear_xmas_kernel(inp: Array2D<&char>) -> usize {
-    let inp: String = vec![inp[0][0], inp[1][1], inp[2][2], inp[3][3]]
-        .into_iter()
-        .collect();
-    if inp == "XMAS" || inp == "SAMX" { 1 } else { 0 }
+/// Every non-wildcard cell of every X-MAS, once per match it's part of.
+fn x_mas_cells(puzzle: &Array2D<char>) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    for pattern in Pattern::parse(X_MAS).orientations() {
+        for (row, col) in pattern.find(puzzle) {
+            cells.extend(
+                pattern
+                    .fixed_cells()
+                    .map(|((d_row, d_col), _)| (row + d_row, col + d_col)),
+            );
+        }
+    }
+    cells
 }
 
-fn cross_xmas_count_kernel(inp: Array2D<&char>) -> usize {
-    if inp[1][1] != &'A' {
-        0
-    } else {
-        let is_primary =
-            inp[0][0].eq(&'M') && inp[2][2].eq(&'S') || inp[0][0].eq(&'S') && inp[2][2].eq(&'M');
-        let is_secondary =
-            inp[2][0].eq(&'M') && inp[0][2].eq(&'S') || inp[2][0].eq(&'S') && inp[0][2].eq(&'M');
-        if is_primary && is_secondary { 1 } else { 0 }
+/// Draws the puzzle like its illustrations, with every letter that isn't part of a match as `.`.
+/// With `color`, letters shared by two matches are yellow and by more than two red.
+fn render(
+    puzzle: &Array2D<char>,
+    cells: impl IntoIterator<Item = (usize, usize)>,
+    color: bool,
+) -> String {
+    let mut n_matches = HashMap::new();
+    for cell in cells {
+        *n_matches.entry(cell).or_insert(0) += 1;
     }
+    (0..puzzle.n_rows())
+        .map(|row| {
+            (0..puzzle.n_cols())
+                .map(|col| {
+                    let letter = puzzle[row][col];
+                    match n_matches.get(&(row, col)) {
+                        None => ".".to_string(),
+                        Some(2) if color => format!("\x1b[33m{letter}\x1b[0m"),
+                        Some(3..) if color => format!("\x1b[31m{letter}\x1b[0m"),
+                        Some(_) => letter.to_string(),
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[cfg(test)]
@@ -71,6 +100,52 @@ mod test {
     fn test_part_2() {
         assert_eq!(part_2(INPUT), 1737)
     }
+
+    #[test]
+    fn test_render() {
+        let puzzle = Array2D::from_rows(EXAMPLE.lines().map(str::chars));
+        assert_eq!(
+            render(&puzzle, xmas_cells(&puzzle), false),
+            "....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX"
+        );
+        assert_eq!(
+            render(&puzzle, x_mas_cells(&puzzle), false),
+            ".M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+.........."
+        );
+        assert_eq!(
+            render(&puzzle, xmas_cells(&puzzle), true).lines().nth(2),
+            Some("...\x1b[33mS\x1b[0m..\x1b[33mA\x1b[0m...")
+        );
+    }
+
+    const EXAMPLE: &str = r#"MMMSXXMASM
+MSAMXMSMSA
+AMXSXMAAMM
+MSAMASMSMX
+XMASAMXAMM
+XXAMMXXAMA
+SMSMSASXSS
+SAXAMASAAA
+MAMMMXMMMM
+MXMXAXMASX"#;
 }
 
 #[allow(clippy::items_after_test_module)]
@@ -1,58 +1,141 @@
+use adventofcode_2024::{Args, bench};
 use std::collections::HashSet;
 
+const MAX_PAGE: usize = 100;
+
 fn main() {
-    println!("{}", part_1(INPUT));
-    println!("{}", part_2(INPUT));
+    let args = Args::from_env();
+    let input = Day5Input::from(INPUT);
+    if args.flag("--verbose") {
+        input
+            .updates
+            .iter()
+            .filter(|update| !update.is_valid(&input.rules))
+            .for_each(|update| println!("{}", update.diagnose(&input.rules)));
+    }
+    if args.flag("--bench") {
+        bench_rule_index(INPUT, &input);
+    }
+    println!("{}", part_1(&input));
+    println!("{}", part_2(&input));
 }
 
-fn part_1(input: &str) -> usize {
-    let rules = RuleSet::from(input);
+fn part_1(input: &Day5Input) -> usize {
     input
-        .lines()
-        .filter(|l| l.contains(","))
-        .map(Update::from)
-        .filter(|update| update.is_valid(&rules))
-        .map(|update| update.middle_page())
+        .updates
+        .iter()
+        .filter(|update| update.is_valid(&input.rules))
+        .map(Update::middle_page)
         .sum()
 }
 
-fn part_2(input: &str) -> usize {
-    let rules = RuleSet::from(input);
+fn part_2(input: &Day5Input) -> usize {
     input
-        .lines()
-        .filter(|l| l.contains(","))
-        .map(Update::from)
-        .filter(|update| !update.is_valid(&rules))
-        .map(|update| update.correct(&rules).middle_page())
+        .updates
+        .iter()
+        .filter(|update| !update.is_valid(&input.rules))
+        .map(|update| update.clone().correct(&input.rules).middle_page())
         .sum()
 }
 
-struct RuleSet(HashSet<(usize, usize)>);
+/// Compares correcting every update against the old `HashSet` of rule pairs.
+fn bench_rule_index(raw: &str, input: &Day5Input) {
+    let pairs: HashSet<(usize, usize)> = raw
+        .lines()
+        .filter_map(|l| l.split_once("|"))
+        .map(|(left, right)| (left.parse().unwrap(), right.parse().unwrap()))
+        .collect();
+    let hashed_cmp = |left: usize, right: usize| {
+        if pairs.contains(&(left, right)) {
+            std::cmp::Ordering::Less
+        } else if pairs.contains(&(right, left)) {
+            std::cmp::Ordering::Greater
+        } else {
+            std::cmp::Ordering::Equal
+        }
+    };
+
+    let sort_all = |cmp: &dyn Fn(usize, usize) -> std::cmp::Ordering| {
+        input
+            .updates
+            .iter()
+            .map(|update| {
+                let mut pages = update.0.clone();
+                pages.sort_by(|left, right| cmp(*left, *right));
+                Update(pages).middle_page()
+            })
+            .sum::<usize>()
+    };
+
+    let hashed = bench("HashSet rules", 1_000, || sort_all(&hashed_cmp));
+    let indexed = bench("bit-matrix rules", 1_000, || {
+        sort_all(&|left, right| input.rules.cmp(left, right))
+    });
+    println!(
+        "speedup: {:.1}x",
+        hashed.as_secs_f64() / indexed.as_secs_f64()
+    );
+}
+
+struct Day5Input {
+    rules: RuleSet,
+    updates: Vec<Update>,
+}
+
+impl From<&str> for Day5Input {
+    fn from(input: &str) -> Self {
+        Self {
+            rules: RuleSet::from(input),
+            updates: input
+                .lines()
+                .filter(|l| l.contains(","))
+                .map(Update::from)
+                .collect(),
+        }
+    }
+}
+
+/// Row `left` has bit `right` set when the rule `left|right` exists. Pages are two-digit, so
+/// every lookup is a single shift into a fixed array.
+struct RuleSet([u128; MAX_PAGE]);
 
 impl RuleSet {
     fn cmp(&self, left: usize, right: usize) -> std::cmp::Ordering {
-        if self.0.contains(&(left, right)) {
+        if self.contains(left, right) {
             std::cmp::Ordering::Less
-        } else if self.0.contains(&(right, left)) {
+        } else if self.contains(right, left) {
             std::cmp::Ordering::Greater
         } else {
             std::cmp::Ordering::Equal
         }
     }
+
+    fn contains(&self, left: usize, right: usize) -> bool {
+        self.0[left] >> right & 1 == 1
+    }
 }
 
 impl From<&str> for RuleSet {
     fn from(input: &str) -> Self {
-        Self(
-            input
-                .lines()
-                .filter_map(|l| l.split_once("|"))
-                .map(|(left, right)| (left.parse().unwrap(), right.parse().unwrap()))
-                .collect(),
-        )
+        input
+            .lines()
+            .filter_map(|l| l.split_once("|"))
+            .map(|(left, right)| (left.parse().unwrap(), right.parse().unwrap()))
+            .fold(
+                Self([0; MAX_PAGE]),
+                |mut rules, (left, right): (usize, usize)| {
+                    assert!(
+                        left < MAX_PAGE && right < MAX_PAGE,
+                        "Pages must be two-digit!"
+                    );
+                    rules.0[left] |= 1 << right;
+                    rules
+                },
+            )
     }
 }
 
+#[derive(Clone)]
 struct Update(Vec<usize>);
 
 impl Update {
@@ -71,11 +154,151 @@ impl Update {
         pages.sort_by(|left, right| rules.cmp(*left, *right));
         Self(pages)
     }
+
+    fn diagnose(&self, rules: &RuleSet) -> Diagnosis {
+        Diagnosis {
+            pages: self.0.clone(),
+            violations: self.violations(rules),
+            moves: self.fixing_moves(rules),
+        }
+    }
+
+    fn violations(&self, rules: &RuleSet) -> Vec<Violation> {
+        let pages = &self.0;
+        (0..pages.len())
+            .flat_map(|i| ((i + 1)..pages.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| rules.cmp(pages[i], pages[j]) == std::cmp::Ordering::Greater)
+            .map(|(i, j)| Violation {
+                earlier: (i, pages[i]),
+                later: (j, pages[j]),
+            })
+            .collect()
+    }
+
+    /// Pages outside a longest subsequence that is already correctly ordered are exactly the
+    /// ones that have to move, so this is the smallest possible set of moves.
+    fn fixing_moves(&self, rules: &RuleSet) -> Vec<Move> {
+        let corrected = Self(self.0.clone()).correct(rules);
+        let targets = self
+            .0
+            .iter()
+            .map(|page| corrected.0.iter().position(|p| p == page).unwrap())
+            .collect::<Vec<_>>();
+
+        // lengths[i] is the length of the longest increasing run of targets ending at i, and
+        // parents[i] the index before it in that run.
+        let mut lengths = vec![1; targets.len()];
+        let mut parents = vec![None; targets.len()];
+        for i in 0..targets.len() {
+            for j in 0..i {
+                if targets[j] < targets[i] && lengths[j] + 1 > lengths[i] {
+                    lengths[i] = lengths[j] + 1;
+                    parents[i] = Some(j);
+                }
+            }
+        }
+
+        let mut in_place = HashSet::new();
+        let mut cursor = (0..targets.len()).max_by_key(|&i| lengths[i]);
+        while let Some(i) = cursor {
+            in_place.insert(i);
+            cursor = parents[i];
+        }
+
+        // Moving pages in the order of their final positions means the page before each one in
+        // the corrected update is already where it belongs, so the page can go right after it.
+        let mut misplaced = (0..targets.len())
+            .filter(|i| !in_place.contains(i))
+            .collect::<Vec<_>>();
+        misplaced.sort_by_key(|&i| targets[i]);
+        let mut pages = self.0.clone();
+        misplaced
+            .into_iter()
+            .map(|i| {
+                let page = self.0[i];
+                let from = pages.iter().position(|&p| p == page).unwrap();
+                pages.remove(from);
+                let to = match targets[i] {
+                    0 => 0,
+                    target => {
+                        let before = corrected.0[target - 1];
+                        pages.iter().position(|&p| p == before).unwrap() + 1
+                    }
+                };
+                pages.insert(to, page);
+                Move { page, from, to }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct Diagnosis {
+    pages: Vec<usize>,
+    violations: Vec<Violation>,
+    moves: Vec<Move>,
+}
+
+impl std::fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pages = self
+            .pages
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(
+            f,
+            "update {pages}: {} violation(s), fixed by {} move(s)",
+            self.violations.len(),
+            self.moves.len()
+        )?;
+        for violation in &self.violations {
+            writeln!(f, "  {violation}")?;
+        }
+        for Move { page, from, to } in &self.moves {
+            writeln!(f, "  move {page} from position {from} to position {to}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A pair of pages in the wrong order, each given as `(position, page)`.
+#[derive(Debug, PartialEq)]
+struct Violation {
+    earlier: (usize, usize),
+    later: (usize, usize),
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (earlier_idx, earlier) = self.earlier;
+        let (later_idx, later) = self.later;
+        write!(
+            f,
+            "page {earlier} (position {earlier_idx}) appears before {later} (position {later_idx}) \
+             but rule {later}|{earlier} requires the opposite"
+        )
+    }
+}
+
+/// Takes a page out of position `from` and inserts it at position `to` of what's left. Moves are
+/// applied in order, each to the update as the moves before it left it.
+#[derive(Debug, PartialEq)]
+struct Move {
+    page: usize,
+    from: usize,
+    to: usize,
 }
 
 impl From<&str> for Update {
     fn from(input: &str) -> Self {
-        Self(input.split(",").map(|page| page.parse().unwrap()).collect())
+        let pages: Vec<usize> = input.split(",").map(|page| page.parse().unwrap()).collect();
+        assert!(
+            pages.iter().all(|&page| page < MAX_PAGE),
+            "Pages must be two-digit!"
+        );
+        Self(pages)
     }
 }
 
@@ -85,13 +308,82 @@ mod test {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1(INPUT), 5713)
+        assert_eq!(part_1(&Day5Input::from(INPUT)), 5713)
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2(INPUT), 5180)
+        assert_eq!(part_2(&Day5Input::from(INPUT)), 5180)
     }
+
+    #[test]
+    fn test_diagnose() {
+        let rules = RuleSet::from(EXAMPLE);
+
+        let diagnosis = Update::from("75,97,47,61,53").diagnose(&rules);
+        assert_eq!(
+            diagnosis.violations,
+            vec![Violation {
+                earlier: (0, 75),
+                later: (1, 97)
+            }]
+        );
+        assert_eq!(diagnosis.moves.len(), 1);
+
+        let diagnosis = Update::from("97,13,75,29,47").diagnose(&rules);
+        assert_eq!(diagnosis.violations.len(), 4);
+        assert_eq!(
+            diagnosis.moves,
+            vec![
+                Move {
+                    page: 29,
+                    from: 3,
+                    to: 4
+                },
+                Move {
+                    page: 13,
+                    from: 1,
+                    to: 4
+                },
+            ]
+        );
+
+        let mut pages = vec![97, 13, 75, 29, 47];
+        for Move { page, from, to } in diagnosis.moves {
+            assert_eq!(pages.remove(from), page);
+            pages.insert(to, page);
+        }
+        assert_eq!(pages, vec![97, 75, 47, 29, 13]);
+
+        assert!(
+            Update::from("75,47,61,53,29")
+                .diagnose(&rules)
+                .violations
+                .is_empty()
+        );
+    }
+
+    const EXAMPLE: &str = r#"47|53
+97|13
+97|61
+97|47
+75|29
+61|13
+75|53
+29|13
+97|29
+53|29
+61|53
+97|53
+61|29
+47|13
+75|47
+97|75
+47|61
+75|61
+47|29
+75|13
+53|13"#;
 }
 
 #[allow(clippy::items_after_test_module)]
@@ -1,58 +1,171 @@
-use std::collections::HashSet;
+use adventofcode_2024::{Args, Parallel, ParallelError};
+use std::collections::{HashMap, HashSet};
 
-const MAX_STEPS: usize = 10_000;
+const DEFAULT_STEP_LIMIT: usize = 1_000_000;
+const DEFAULT_FRAME_LIMIT: usize = 100;
 
 fn main() {
-    println!("{}", part_1(INPUT));
-    println!("{}", part_2(INPUT));
-}
-
-fn part_1(input: &str) -> usize {
-    Map::from_str(input)
+    let args = Args::from_env();
+    let step_limit = args
+        .value("--step-limit")
+        .map_or(DEFAULT_STEP_LIMIT, |limit| limit.parse().unwrap());
+    let map = Map::from_str(INPUT)
         .build()
-        .run()
         .unwrap()
-        .guard
-        .previous
+        .with_step_limit(step_limit);
+    let parallel = match args.value("--threads") {
+        Some(n_threads) => Parallel::new().with_threads(n_threads.parse().unwrap()),
+        None => Parallel::new(),
+    };
+    if args.flag("--loops") {
+        match loop_placements(&map, &parallel) {
+            Ok(placements) => placements
+                .iter()
+                .for_each(|placement| println!("{placement}")),
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+    if let Some(obstacle) = args.value("--show") {
+        let (i, j) = obstacle.split_once(",").unwrap();
+        let obstacle = (i.parse().unwrap(), j.parse().unwrap());
+        match map.run(Some(obstacle)) {
+            Outcome::Exited { path } | Outcome::Looped { path, .. } => {
+                println!("{}", map.render(&path, Some(obstacle)))
+            }
+            Outcome::StepLimit => eprintln!("{}", StepLimitReached(obstacle)),
+        }
+    }
+    if args.flag("--trace") || args.flag("--trace-frames") {
+        let frame_limit = args
+            .value("--frames")
+            .map_or(DEFAULT_FRAME_LIMIT, |limit| limit.parse().unwrap());
+        let mut visited = HashSet::new();
+        for (idx, step) in map.walk().take(frame_limit).enumerate() {
+            visited.insert(step.guard.position);
+            println!("{idx}: {step}");
+            if args.flag("--trace-frames") {
+                println!("{}\n", map.render_frame(&step.guard, &visited));
+            }
+        }
+    }
+    match part_1(&map) {
+        Ok(answer) => println!("{answer}"),
+        Err(error) => eprintln!("{error}"),
+    }
+    match part_2(&map, &parallel) {
+        Ok(answer) => println!("{answer}"),
+        Err(error) => eprintln!("{error}"),
+    }
+}
+
+fn part_1(map: &Map) -> Result<usize, RouteError> {
+    Ok(map
+        .run(None)
+        .exited_path()?
+        .cells()
         .into_iter()
-        .map(|(position, _)| position)
+        .map(|guard| guard.position)
         .collect::<HashSet<_>>()
-        .len()
+        .len())
 }
 
-fn part_2(input: &str) -> usize {
-    let map = Map::from_str(input).build();
-    let placements = map
-        .clone()
-        .run()
-        .unwrap()
-        .guard
-        .previous
-        .into_iter()
-        .map(|(position, _)| position)
-        .collect::<HashSet<_>>();
-    let n_threads: usize = std::thread::available_parallelism().unwrap().into();
-    let handles = (0..n_threads)
-        .map(|thread_idx| {
-            let map = map.clone();
-            let placements = placements.clone();
-            std::thread::spawn(move || {
-                placements
-                    .iter()
-                    .skip(thread_idx)
-                    .step_by(n_threads)
-                    .map(|position| {
-                        if let Ok(map) = map.clone().place_obstacle(position.to_owned()) {
-                            if map.run().is_err() { 1 } else { 0 }
-                        } else {
-                            0
-                        }
-                    })
-                    .sum::<usize>()
-            })
-        })
+fn part_2(map: &Map, parallel: &Parallel) -> Result<usize, RouteError> {
+    Ok(loop_placements(map, parallel)?.len())
+}
+
+/// Only cells on the original path can change the guard's route, and an obstacle there is first
+/// met when the guard is about to enter that cell, so each simulation starts from that point.
+/// That tells whether the guard loops, but not where it enters the loop, so placements that loop
+/// are run again from the guard's real start.
+fn loop_placements(map: &Map, parallel: &Parallel) -> Result<Vec<LoopPlacement>, RouteError> {
+    let cells = map.run(None).exited_path()?.cells();
+    let mut visited = HashSet::from([map.guard.position]);
+    let placements = cells
+        .windows(2)
+        .filter(|window| visited.insert(window[1].position))
+        .map(|window| (window[0], window[1].position))
         .collect::<Vec<_>>();
-    handles.into_iter().map(|h| h.join().unwrap()).sum()
+
+    let mut loops = parallel.map_reduce(
+        &placements,
+        |(start, obstacle)| {
+            let outcome = match map.simulate(*start, Some(*obstacle)) {
+                Outcome::Looped { .. } => map.run(Some(*obstacle)),
+                outcome => outcome,
+            };
+            match outcome {
+                Outcome::Exited { .. } => Ok(None),
+                Outcome::Looped {
+                    cycle_start,
+                    cycle_len,
+                    ..
+                } => Ok(Some(LoopPlacement {
+                    obstacle: *obstacle,
+                    cycle_start,
+                    cycle_len,
+                })),
+                Outcome::StepLimit => Err(StepLimitReached(*obstacle)),
+            }
+        },
+        Vec::new(),
+        |mut loops, placement| {
+            loops.extend(placement);
+            loops
+        },
+    )?;
+    loops.sort_by_key(|placement| placement.obstacle);
+    Ok(loops)
+}
+
+/// Why the guard's route, or the loops an obstacle could cause, can't be worked out.
+#[derive(Debug, PartialEq)]
+enum RouteError {
+    /// Without any extra obstacle the guard doesn't leave the map.
+    NeverExited(Outcome),
+    Placement(ParallelError<StepLimitReached>),
+}
+
+impl From<ParallelError<StepLimitReached>> for RouteError {
+    fn from(error: ParallelError<StepLimitReached>) -> Self {
+        Self::Placement(error)
+    }
+}
+
+impl std::fmt::Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NeverExited(outcome) => write!(f, "the guard never left the map: {outcome}"),
+            Self::Placement(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct StepLimitReached(Position);
+
+impl std::fmt::Display for StepLimitReached {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "an obstacle at {:?} reached the step limit", self.0)
+    }
+}
+
+/// An obstacle that traps the guard, with the state the guard first repeats and how many steps
+/// it takes to come back to it.
+#[derive(Debug)]
+struct LoopPlacement {
+    obstacle: Position,
+    cycle_start: Guard,
+    cycle_len: usize,
+}
+
+impl std::fmt::Display for LoopPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "obstacle at {:?}: loop entered at {:?} facing {:?}, cycle of {} step(s)",
+            self.obstacle, self.cycle_start.position, self.cycle_start.direction, self.cycle_len
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -60,161 +173,445 @@ struct Map {
     guard: Guard,
     obstacles: HashSet<Position>,
     bounds: Position,
+    jumps: JumpTable,
+    step_limit: usize,
 }
 
 impl Map {
     fn from_str(input: &str) -> MapBuilder {
+        let n_rows = input.lines().count() as isize;
+        let n_cols = input.lines().map(str::len).max().unwrap_or(0) as isize;
         input
             .lines()
             .enumerate()
             .flat_map(|(i, line)| line.chars().enumerate().map(move |(j, c)| (i, j, c)))
-            .fold(MapBuilder::new(), |mut builder, (i, j, c)| {
-                match c {
-                    '#' => builder.add_obstacle((i as isize, j as isize)),
-                    '^' => builder.add_guard((i as isize, j as isize), Direction::Up),
-                    '>' => builder.add_guard((i as isize, j as isize), Direction::Right),
-                    'v' => builder.add_guard((i as isize, j as isize), Direction::Down),
-                    '<' => builder.add_guard((i as isize, j as isize), Direction::Left),
-                    _ => {}
+            .fold(
+                MapBuilder::new((n_rows - 1, n_cols - 1)),
+                |mut builder, (i, j, c)| {
+                    match c {
+                        '#' => builder.add_obstacle((i as isize, j as isize)),
+                        '^' => builder.add_guard((i as isize, j as isize), Direction::Up),
+                        '>' => builder.add_guard((i as isize, j as isize), Direction::Right),
+                        'v' => builder.add_guard((i as isize, j as isize), Direction::Down),
+                        '<' => builder.add_guard((i as isize, j as isize), Direction::Left),
+                        _ => {}
+                    };
+                    builder
+                },
+            )
+    }
+
+    fn with_step_limit(self, step_limit: usize) -> Self {
+        Self { step_limit, ..self }
+    }
+
+    fn run(&self, extra_obstacle: Option<Position>) -> Outcome {
+        self.simulate(self.guard, extra_obstacle)
+    }
+
+    /// Jumps straight from one turn to the next. Since the jump table doesn't know about
+    /// `extra_obstacle`, each jump checks whether it lies on the way first. A loop is detected
+    /// as soon as the guard turns in a state it has already turned in.
+    fn simulate(&self, start: Guard, extra_obstacle: Option<Position>) -> Outcome {
+        let mut guard = start;
+        let mut states = vec![guard];
+        let mut turns = HashMap::new();
+        let mut n_steps = 0;
+        loop {
+            let distance = self.jumps.distance(&guard, &self.bounds);
+            let (stop, distance) =
+                match extra_obstacle.and_then(|obstacle| guard.distance_to(obstacle)) {
+                    Some(to_obstacle) if distance >= to_obstacle => {
+                        (Some(guard.advance(to_obstacle - 1).position), to_obstacle)
+                    }
+                    _ => (self.jumps.stop(&guard), distance),
                 };
-                builder
-            })
+
+            // The guard turns in place instead of stepping into the obstacle, but does step off
+            // the map, so both count as one step.
+            n_steps += distance;
+            if n_steps > self.step_limit {
+                return Outcome::StepLimit;
+            }
+
+            match stop {
+                Some(position) => {
+                    guard = Guard {
+                        position,
+                        direction: guard.direction.turn_right(),
+                    };
+                    states.push(guard);
+                    if let Some(previous) = turns.insert(guard, n_steps) {
+                        return Outcome::Looped {
+                            path: Path(states),
+                            cycle_start: guard,
+                            cycle_len: n_steps - previous,
+                        };
+                    }
+                }
+                None => {
+                    states.push(guard.advance(distance - 1));
+                    return Outcome::Exited { path: Path(states) };
+                }
+            }
+        }
     }
 
-    fn place_obstacle(mut self, position: Position) -> Result<Self, Occupied> {
-        if self.obstacles.contains(&position) {
-            Err(Occupied)
-        } else {
-            self.obstacles.insert(position);
-            Ok(self)
+    /// Steps the guard one cell or turn at a time. This never ends if the guard is stuck in a loop.
+    fn walk(&self) -> Walk<'_> {
+        Walk {
+            map: self,
+            next: Some(Step {
+                guard: self.guard,
+                action: Action::Start,
+            }),
         }
     }
 
-    fn run(mut self) -> Result<Self, Loop> {
-        for _ in 0..MAX_STEPS {
-            if self.is_loop() {
-                return Err(Loop);
+    /// Draws a single frame of a walk, with the guard as `^>v<` and the cells it has visited as
+    /// `X`.
+    fn render_frame(&self, guard: &Guard, visited: &HashSet<Position>) -> String {
+        (0..=self.bounds.0)
+            .map(|i| {
+                (0..=self.bounds.1)
+                    .map(|j| {
+                        let position = (i, j);
+                        if position == guard.position {
+                            guard.direction.symbol()
+                        } else if self.obstacles.contains(&position) {
+                            '#'
+                        } else if visited.contains(&position) {
+                            'X'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Draws the grid like the puzzle text: `|`, `-` and `+` along the path, the guard's starting
+    /// direction at its start, and `extra_obstacle` as `O`.
+    fn render(&self, path: &Path, extra_obstacle: Option<Position>) -> String {
+        let mut vertical = HashSet::new();
+        let mut horizontal = HashSet::new();
+        for guard in path.cells() {
+            match guard.direction {
+                Direction::Up | Direction::Down => vertical.insert(guard.position),
+                Direction::Right | Direction::Left => horizontal.insert(guard.position),
             };
-            if self.is_over() {
-                return Ok(self);
-            }
-            self = self.step();
         }
-        panic!("A simulation reached MAX_STEPS!")
+        (0..=self.bounds.0)
+            .map(|i| {
+                (0..=self.bounds.1)
+                    .map(|j| {
+                        let position = (i, j);
+                        if position == self.guard.position {
+                            self.guard.direction.symbol()
+                        } else if Some(position) == extra_obstacle {
+                            'O'
+                        } else if self.obstacles.contains(&position) {
+                            '#'
+                        } else {
+                            match (vertical.contains(&position), horizontal.contains(&position)) {
+                                (true, true) => '+',
+                                (true, false) => '|',
+                                (false, true) => '-',
+                                (false, false) => '.',
+                            }
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
+}
 
-    fn step(mut self) -> Self {
-        self.guard = if self.obstacles.contains(&self.guard.next_position()) {
-            self.guard.turn_right()
+struct Walk<'a> {
+    map: &'a Map,
+    next: Option<Step>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        let guard = current.guard;
+        let ahead = guard.advance(1);
+        self.next = if self.map.obstacles.contains(&ahead.position) {
+            Some(Step {
+                guard: Guard {
+                    direction: guard.direction.turn_right(),
+                    ..guard
+                },
+                action: Action::Turned,
+            })
+        } else if ahead.is_in_bounds(&self.map.bounds) {
+            Some(Step {
+                guard: ahead,
+                action: Action::Moved,
+            })
         } else {
-            self.guard.step()
+            None
         };
-        self
+        Some(current)
     }
+}
 
-    fn is_over(&self) -> bool {
-        !self.guard.is_in_bounds(&self.bounds)
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Step {
+    guard: Guard,
+    action: Action,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.action {
+            Action::Start => "started at",
+            Action::Moved => "moved to",
+            Action::Turned => "turned at",
+        };
+        write!(
+            f,
+            "{action} {:?} facing {:?}",
+            self.guard.position, self.guard.direction
+        )
     }
+}
 
-    fn is_loop(&self) -> bool {
-        let guard_state = (
-            self.guard.position.to_owned(),
-            self.guard.direction.to_owned(),
-        );
-        self.guard.previous.contains(&guard_state)
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    Start,
+    Moved,
+    Turned,
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Exited {
+        path: Path,
+    },
+    Looped {
+        path: Path,
+        cycle_start: Guard,
+        cycle_len: usize,
+    },
+    StepLimit,
+}
+
+impl Outcome {
+    fn exited_path(self) -> Result<Path, RouteError> {
+        match self {
+            Self::Exited { path } => Ok(path),
+            outcome => Err(RouteError::NeverExited(outcome)),
+        }
     }
 }
 
-#[derive(Debug)]
-struct Loop;
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exited { path } => write!(f, "exited after {} turn(s)", path.0.len() - 2),
+            Self::Looped {
+                cycle_start,
+                cycle_len,
+                ..
+            } => write!(
+                f,
+                "looped from {:?} facing {:?} every {cycle_len} step(s)",
+                cycle_start.position, cycle_start.direction
+            ),
+            Self::StepLimit => write!(f, "reached the step limit"),
+        }
+    }
+}
 
-#[derive(Debug)]
-struct Occupied;
+/// Every state the guard turns in, bracketed by its start and the last cell before it leaves the
+/// map.
+#[derive(Debug, PartialEq)]
+struct Path(Vec<Guard>);
+
+impl Path {
+    /// Expands the path into one state per step, including turns in place.
+    fn cells(&self) -> Vec<Guard> {
+        let mut cells = vec![self.0[0]];
+        for pair in self.0.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let distance = from.distance_to(to.position).unwrap_or(0);
+            cells.extend((1..=distance).map(|k| from.advance(k)));
+            if from.direction != to.direction {
+                cells.push(to);
+            }
+        }
+        cells
+    }
+}
+
+/// For every cell and direction, the cell the guard stops in before the next obstacle, or `None`
+/// if it would walk off the map.
+#[derive(Clone, Debug)]
+struct JumpTable {
+    n_cols: usize,
+    stops: Vec<[Option<Position>; 4]>,
+}
+
+impl JumpTable {
+    fn new(obstacles: &HashSet<Position>, bounds: &Position) -> Self {
+        let n_rows = bounds.0 as usize + 1;
+        let n_cols = bounds.1 as usize + 1;
+        let mut stops = vec![[None; 4]; n_rows * n_cols];
+        for direction in Direction::ALL {
+            let (di, dj) = direction.delta();
+            // Sweep against the direction of travel so the nearest obstacle ahead is known.
+            let rows = (0..n_rows as isize).collect::<Vec<_>>();
+            let cols = (0..n_cols as isize).collect::<Vec<_>>();
+            let rows = if di > 0 {
+                rows.into_iter().rev().collect()
+            } else {
+                rows
+            };
+            let cols = if dj > 0 {
+                cols.into_iter().rev().collect()
+            } else {
+                cols
+            };
+            for &i in &rows {
+                for &j in &cols {
+                    let ahead = (i + di, j + dj);
+                    let stop = if obstacles.contains(&ahead) {
+                        Some((i, j))
+                    } else if (0..n_rows as isize).contains(&ahead.0)
+                        && (0..n_cols as isize).contains(&ahead.1)
+                    {
+                        stops[ahead.0 as usize * n_cols + ahead.1 as usize][direction as usize]
+                    } else {
+                        None
+                    };
+                    stops[i as usize * n_cols + j as usize][direction as usize] = stop;
+                }
+            }
+        }
+        Self { n_cols, stops }
+    }
+
+    fn stop(&self, guard: &Guard) -> Option<Position> {
+        let (i, j) = guard.position;
+        self.stops[i as usize * self.n_cols + j as usize][guard.direction as usize]
+    }
+
+    /// Number of steps until the guard would enter the next obstacle or leave the map.
+    fn distance(&self, guard: &Guard, bounds: &Position) -> usize {
+        let end = self
+            .stop(guard)
+            .unwrap_or_else(|| guard.position_at_edge(bounds));
+        guard.distance_to(end).unwrap_or(0) + 1
+    }
+}
 
 struct MapBuilder {
-    guard: Option<Guard>,
+    guards: Vec<Guard>,
     obstacles: HashSet<Position>,
     bounds: Position,
 }
 
 impl MapBuilder {
-    fn new() -> Self {
+    fn new(bounds: Position) -> Self {
         Self {
-            guard: None,
+            guards: Vec::new(),
             obstacles: HashSet::new(),
-            bounds: (0, 0),
+            bounds,
         }
     }
 
-    fn build(self) -> Map {
-        Map {
-            guard: self.guard.unwrap(),
+    fn build(self) -> Result<Map, MapError> {
+        let guard = match self.guards.as_slice() {
+            [] => return Err(MapError::NoGuard),
+            [guard] => *guard,
+            guards => {
+                return Err(MapError::MultipleGuards(
+                    guards.iter().map(|guard| guard.position).collect(),
+                ));
+            }
+        };
+        Ok(Map {
+            guard,
+            jumps: JumpTable::new(&self.obstacles, &self.bounds),
+            step_limit: DEFAULT_STEP_LIMIT,
             obstacles: self.obstacles,
             bounds: self.bounds,
-        }
+        })
     }
 
     fn add_obstacle(&mut self, position: Position) {
         self.obstacles.insert(position);
-        self.bounds.0 = std::cmp::max(self.bounds.0, position.0);
-        self.bounds.1 = std::cmp::max(self.bounds.1, position.1);
     }
 
     fn add_guard(&mut self, position: Position, direction: Direction) {
-        self.guard = Some(Guard {
+        self.guards.push(Guard {
             position,
             direction,
-            previous: HashSet::new(),
         })
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug, PartialEq)]
+enum MapError {
+    NoGuard,
+    MultipleGuards(Vec<Position>),
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 struct Guard {
     position: Position,
     direction: Direction,
-    previous: HashSet<(Position, Direction)>,
 }
 
 impl Guard {
-    fn turn_right(self) -> Self {
+    fn advance(&self, distance: usize) -> Self {
+        let (i, j) = self.position;
+        let (di, dj) = self.direction.delta();
+        let distance = distance as isize;
         Self {
-            direction: self.direction.turn_right(),
-            ..self
+            position: (i + di * distance, j + dj * distance),
+            ..*self
         }
     }
 
-    fn step(mut self) -> Self {
-        let next = self.next_position();
-        self.previous
-            .insert((self.position, self.direction.clone()));
-        Self {
-            position: next,
-            ..self
+    /// Number of steps straight ahead to reach `position`, if it is strictly ahead of the guard.
+    fn distance_to(&self, position: Position) -> Option<usize> {
+        let (di, dj) = self.direction.delta();
+        let (oi, oj) = (position.0 - self.position.0, position.1 - self.position.1);
+        let distance = oi * di + oj * dj;
+        if distance > 0 && (oi, oj) == (di * distance, dj * distance) {
+            Some(distance as usize)
+        } else {
+            None
         }
     }
 
-    fn next_position(&self) -> Position {
+    fn is_in_bounds(&self, bounds: &Position) -> bool {
         let (i, j) = self.position;
-        match self.direction {
-            Direction::Up => (i - 1, j),
-            Direction::Right => (i, j + 1),
-            Direction::Down => (i + 1, j),
-            Direction::Left => (i, j - 1),
-        }
+        (0..=bounds.0).contains(&i) && (0..=bounds.1).contains(&j)
     }
 
-    fn is_in_bounds(&self, bounds: &Position) -> bool {
-        let (i, j) = &self.position;
-        let i_range = 0..=bounds.0;
-        let j_range = 0..=bounds.1;
-        i_range.contains(i) && j_range.contains(j)
+    fn position_at_edge(&self, bounds: &Position) -> Position {
+        let (i, j) = self.position;
+        match self.direction {
+            Direction::Up => (0, j),
+            Direction::Right => (i, bounds.1),
+            Direction::Down => (bounds.0, j),
+            Direction::Left => (i, 0),
+        }
     }
 }
 
 type Position = (isize, isize);
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 enum Direction {
     Up,
     Down,
@@ -223,6 +620,8 @@ enum Direction {
 }
 
 impl Direction {
+    const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Right, Self::Left];
+
     fn turn_right(&self) -> Self {
         match self {
             Self::Up => Self::Right,
@@ -231,6 +630,24 @@ impl Direction {
             Self::Left => Self::Up,
         }
     }
+
+    fn symbol(&self) -> char {
+        match self {
+            Self::Up => '^',
+            Self::Right => '>',
+            Self::Down => 'v',
+            Self::Left => '<',
+        }
+    }
+
+    fn delta(&self) -> Position {
+        match self {
+            Self::Up => (-1, 0),
+            Self::Right => (0, 1),
+            Self::Down => (1, 0),
+            Self::Left => (0, -1),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -239,13 +656,156 @@ mod test {
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1(INPUT), 4374)
+        assert_eq!(part_1(&Map::from_str(INPUT).build().unwrap()), Ok(4374))
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2(INPUT), 1705)
+        assert_eq!(
+            part_2(&Map::from_str(INPUT).build().unwrap(), &Parallel::new()),
+            Ok(1705)
+        )
+    }
+
+    #[test]
+    fn test_run_outcomes() {
+        let map = Map::from_str(EXAMPLE).build().unwrap();
+        assert!(matches!(map.run(None), Outcome::Exited { .. }));
+        assert!(matches!(
+            map.run(Some((6, 3))),
+            Outcome::Looped { cycle_len: 22, .. }
+        ));
+        let map = map.with_step_limit(10);
+        assert!(matches!(map.run(None), Outcome::StepLimit));
+        assert_eq!(
+            part_1(&map),
+            Err(RouteError::NeverExited(Outcome::StepLimit))
+        );
+        assert_eq!(
+            part_2(&map, &Parallel::new()),
+            Err(RouteError::NeverExited(Outcome::StepLimit))
+        );
+    }
+
+    #[test]
+    fn test_bounds_from_grid() {
+        let map = Map::from_str(".#...\n....#\n.^...\n.....\n.....")
+            .build()
+            .unwrap();
+        assert_eq!(map.bounds, (4, 4));
+        assert_eq!(part_1(&map), Ok(7));
+    }
+
+    #[test]
+    fn test_guard_count() {
+        assert_eq!(
+            Map::from_str("..#\n...").build().unwrap_err(),
+            MapError::NoGuard
+        );
+        assert_eq!(
+            Map::from_str("^.#\n..<").build().unwrap_err(),
+            MapError::MultipleGuards(vec![(0, 0), (1, 2)])
+        );
     }
+
+    #[test]
+    fn test_loop_placements() {
+        let map = Map::from_str(EXAMPLE).build().unwrap();
+        let placements = loop_placements(&map, &Parallel::new().with_threads(1))
+            .unwrap()
+            .into_iter()
+            .map(|placement| {
+                (
+                    placement.obstacle,
+                    placement.cycle_start.position,
+                    placement.cycle_start.direction,
+                    placement.cycle_len,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            placements,
+            vec![
+                ((6, 3), (1, 4), Direction::Right, 22),
+                ((7, 6), (6, 2), Direction::Up, 16),
+                ((7, 7), (8, 6), Direction::Left, 16),
+                ((8, 1), (4, 2), Direction::Right, 20),
+                ((8, 3), (1, 4), Direction::Right, 42),
+                ((9, 7), (8, 1), Direction::Up, 18),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let map = Map::from_str(EXAMPLE).build().unwrap();
+        let Outcome::Looped { path, .. } = map.run(Some((6, 3))) else {
+            panic!("Expected a loop!")
+        };
+        assert_eq!(
+            map.render(&path, Some((6, 3))),
+            r#"....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#..."#
+        );
+    }
+
+    #[test]
+    fn test_walk() {
+        let map = Map::from_str(EXAMPLE).build().unwrap();
+        let steps = map.walk().collect::<Vec<_>>();
+        assert_eq!(
+            steps.len(),
+            map.run(None).exited_path().unwrap().cells().len()
+        );
+        assert_eq!(
+            steps[6],
+            Step {
+                guard: Guard {
+                    position: (1, 4),
+                    direction: Direction::Right
+                },
+                action: Action::Turned
+            }
+        );
+
+        let mut visited = HashSet::new();
+        for step in &steps[..3] {
+            visited.insert(step.guard.position);
+        }
+        assert_eq!(
+            map.render_frame(&steps[2].guard, &visited)
+                .lines()
+                .nth(4)
+                .unwrap(),
+            "....^..#.."
+        );
+        assert_eq!(
+            map.render_frame(&steps[2].guard, &visited)
+                .lines()
+                .nth(5)
+                .unwrap(),
+            "....X....."
+        );
+    }
+
+    const EXAMPLE: &str = r#"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#..."#;
 }
 
 #[allow(clippy::items_after_test_module)]
@@ -1,16 +1,74 @@
-use std::collections::VecDeque;
+use adventofcode_2024::{Args, bench};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    ops::ControlFlow,
+};
 
 fn main() {
-    println!("{}", part_1(INPUT));
-    println!("{}", part_2(INPUT));
+    let args = Args::from_env();
+    let operators = parse_operators(args.value("--ops").unwrap_or("+,*,||"));
+    let operators = operators.iter().map(Box::as_ref).collect::<Vec<_>>();
+    let solver = Solver::from_str(args.value("--solver").unwrap_or("reverse"));
+    if args.flag("--solutions") {
+        for eq in INPUT.lines().map(Equation::from_str) {
+            let solutions = eq.solutions(&operators, solver);
+            println!("{}: {} solution(s)", eq.target, solutions.len());
+            for solution in solutions {
+                println!("  {}", eq.render(&solution));
+            }
+        }
+    } else if args.flag("--count") {
+        for eq in INPUT.lines().map(Equation::from_str) {
+            println!(
+                "{}: {} solution(s)",
+                eq.target,
+                eq.count_solutions(&operators, solver)
+            );
+        }
+    }
+    if let Some(numbers) = args.value("--reachable") {
+        let eq = Equation::from_str(&format!("0: {numbers}"));
+        let targets = eq.reachable_targets(&operators);
+        println!("{} reachable target(s): {targets:?}", targets.len());
+    }
+    if args.flag("--bench") {
+        let forward = bench("forward", 10, || {
+            calibration(INPUT, &operators, Solver::Forward)
+        });
+        let reverse = bench("reverse", 10, || {
+            calibration(INPUT, &operators, Solver::Reverse)
+        });
+        let dynamic = bench("dynamic", 10, || {
+            calibration(INPUT, &operators, Solver::Dynamic)
+        });
+        println!(
+            "speedup: reverse {:.1}x, dynamic {:.1}x",
+            forward.as_secs_f64() / reverse.as_secs_f64(),
+            forward.as_secs_f64() / dynamic.as_secs_f64()
+        );
+    }
+    if args.value("--ops").is_some() {
+        println!("{}", calibration(INPUT, &operators, solver));
+    } else {
+        println!("{}", part_1(INPUT, solver));
+        println!("{}", part_2(INPUT, solver));
+    }
 }
 
-fn part_1(input: &str) -> usize {
+fn part_1(input: &str, solver: Solver) -> Number {
+    calibration(input, &[&Add, &Multiply], solver)
+}
+
+fn part_2(input: &str, solver: Solver) -> Number {
+    calibration(input, &[&Add, &Multiply, &Concatenate], solver)
+}
+
+fn calibration(input: &str, available_operators: &[&dyn Operator], solver: Solver) -> Number {
     input
         .lines()
         .map(|l| {
             let eq = Equation::from_str(l);
-            if eq.is_true(&[Operator::Add, Operator::Multiply]) {
+            if eq.is_true(available_operators, solver) {
                 eq.target
             } else {
                 0
@@ -19,25 +77,41 @@ fn part_1(input: &str) -> usize {
         .sum()
 }
 
-fn part_2(input: &str) -> usize {
-    input
-        .lines()
-        .map(|l| {
-            let eq = Equation::from_str(l);
-            if eq.is_true(&[Operator::Add, Operator::Multiply, Operator::Concatenate]) {
-                eq.target
-            } else {
-                0
+/// Parses a comma separated list of operator symbols, like `+,*,||`. Concatenation in another
+/// base is written with the base after the bars, like `||2`.
+fn parse_operators(spec: &str) -> Vec<Box<dyn Operator>> {
+    spec.split(",")
+        .map(str::trim)
+        .map(|symbol| {
+            let mut known: Vec<Box<dyn Operator>> = vec![
+                Box::new(Add),
+                Box::new(Multiply),
+                Box::new(Concatenate),
+                Box::new(Subtract),
+                Box::new(Divide),
+                Box::new(Exponent),
+            ];
+            if let Some(Ok(base)) = symbol.strip_prefix("||").map(str::parse)
+                && base >= 2
+            {
+                known.push(Box::new(BaseConcatenate(base)));
             }
+            known
+                .into_iter()
+                .find(|operator| operator.symbol() == symbol)
+                .unwrap_or_else(|| panic!("Unknown operator {symbol}!"))
         })
-        .sum()
+        .collect()
 }
 
+/// Wide enough for equations whose targets don't fit in 64 bits.
+type Number = u128;
+
 #[derive(Clone)]
 struct Equation {
-    target: usize,
-    numbers: VecDeque<usize>,
-    accumulator: usize,
+    target: Number,
+    numbers: VecDeque<Number>,
+    accumulator: Number,
 }
 
 impl Equation {
@@ -52,60 +126,755 @@ impl Equation {
         }
     }
 
-    fn is_true(&self, available_operators: &[Operator]) -> bool {
+    fn is_true(&self, available_operators: &[&dyn Operator], solver: Solver) -> bool {
+        match solver {
+            Solver::Dynamic => self
+                .layers(available_operators, Some(self.target))
+                .last()
+                .unwrap()
+                .contains_key(&self.target),
+            _ => self.solve(available_operators, solver).is_some(),
+        }
+    }
+
+    /// The first sequence of operators that makes the equation true, if there is one.
+    fn solve<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        solver: Solver,
+    ) -> Option<Vec<&'a dyn Operator>> {
+        let mut solution = None;
+        let _ = self.search(available_operators, solver, &mut |operators| {
+            solution = Some(operators.to_vec());
+            ControlFlow::Break(())
+        });
+        solution
+    }
+
+    fn solutions<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        solver: Solver,
+    ) -> Vec<Vec<&'a dyn Operator>> {
+        let mut solutions = Vec::new();
+        let _ = self.search(available_operators, solver, &mut |operators| {
+            solutions.push(operators.to_vec());
+            ControlFlow::Continue(())
+        });
+        solutions
+    }
+
+    fn count_solutions(&self, available_operators: &[&dyn Operator], solver: Solver) -> usize {
+        if let Solver::Dynamic = solver {
+            return self
+                .layers(available_operators, Some(self.target))
+                .last()
+                .unwrap()
+                .get(&self.target)
+                .copied()
+                .unwrap_or(0);
+        }
+
+        let mut count = 0;
+        let _ = self.search(available_operators, solver, &mut |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    /// Hands each operator sequence that makes the equation true to `visit`, which decides
+    /// whether to keep searching.
+    fn search<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        solver: Solver,
+        visit: &mut dyn FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        match solver {
+            Solver::Forward => self.search_forward(available_operators, &mut Vec::new(), visit),
+            Solver::Reverse => self.search_reverse(
+                available_operators,
+                self.numbers.len() + 1,
+                self.target,
+                &mut Vec::new(),
+                visit,
+            ),
+            Solver::Dynamic => self.search_dynamic(
+                available_operators,
+                &self.layers(available_operators, Some(self.target)),
+                self.numbers.len() + 1,
+                self.target,
+                &mut Vec::new(),
+                visit,
+            ),
+        }
+    }
+
+    fn search_forward<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        chosen: &mut Vec<&'a dyn Operator>,
+        visit: &mut dyn FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
         if self.numbers.is_empty() {
-            return self.accumulator == self.target;
+            if self.accumulator == self.target {
+                return visit(chosen);
+            }
+            return ControlFlow::Continue(());
         }
 
-        if self.accumulator > self.target {
-            return false;
+        if self.accumulator > self.target && self.only_increases(available_operators) {
+            return ControlFlow::Continue(());
         }
 
         for operator in available_operators {
-            if self.operate_next(operator).is_true(available_operators) {
-                return true;
+            if let Some(next) = self.operate_next(*operator) {
+                chosen.push(*operator);
+                let flow = next.search_forward(available_operators, chosen, visit);
+                chosen.pop();
+                flow?;
             }
         }
 
-        false
+        ControlFlow::Continue(())
+    }
+
+    /// Undoes the last of the first `len` numbers from `target` with each operator's inverse.
+    /// Most inverses fail straight away (the target isn't divisible, or doesn't end in the
+    /// number's digits), so whole subtrees are skipped without being explored. Operators that
+    /// can't be undone fall back to trying every prefix forwards. `chosen` holds the operators
+    /// from the end of the equation backwards.
+    fn search_reverse<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        len: usize,
+        target: Number,
+        chosen: &mut Vec<&'a dyn Operator>,
+        visit: &mut dyn FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if len == 1 {
+            if self.accumulator == target {
+                let operators = chosen.iter().rev().copied().collect::<Vec<_>>();
+                return visit(&operators);
+            }
+            return ControlFlow::Continue(());
+        }
+
+        let last = self.numbers[len - 2];
+        for operator in available_operators {
+            chosen.push(*operator);
+            let flow = if operator.absorbs(target, last) {
+                self.search_prefixes(
+                    available_operators,
+                    self.accumulator,
+                    &|_| true,
+                    &mut Vec::new(),
+                    chosen,
+                    visit,
+                )
+            } else if !operator.invertible() {
+                self.search_prefixes(
+                    available_operators,
+                    self.accumulator,
+                    &|previous| operator.apply(previous, last) == Some(target),
+                    &mut Vec::new(),
+                    chosen,
+                    visit,
+                )
+            } else if let Some(previous) = operator.inverse(target, last) {
+                self.search_reverse(available_operators, len - 1, previous, chosen, visit)
+            } else {
+                ControlFlow::Continue(())
+            };
+            chosen.pop();
+            flow?;
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Hands on every operator sequence for the numbers before those in `chosen` whose value
+    /// `reaches` the rest of the equation, followed by the operators in `chosen`. The reverse
+    /// solver uses this when the rest can't be undone to a single value. `value` is what the
+    /// operators in `prefix` have made of the numbers so far.
+    fn search_prefixes<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        value: Number,
+        reaches: &dyn Fn(Number) -> bool,
+        prefix: &mut Vec<&'a dyn Operator>,
+        chosen: &[&'a dyn Operator],
+        visit: &mut dyn FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if prefix.len() + chosen.len() == self.numbers.len() {
+            if !reaches(value) {
+                return ControlFlow::Continue(());
+            }
+            let operators = prefix
+                .iter()
+                .chain(chosen.iter().rev())
+                .copied()
+                .collect::<Vec<_>>();
+            return visit(&operators);
+        }
+
+        let right = self.numbers[prefix.len()];
+        for operator in available_operators {
+            if let Some(next) = operator.apply(value, right) {
+                prefix.push(*operator);
+                let flow =
+                    self.search_prefixes(available_operators, next, reaches, prefix, chosen, visit);
+                prefix.pop();
+                flow?;
+            }
+        }
+
+        ControlFlow::Continue(())
     }
 
-    fn operate_next(&self, operator: &Operator) -> Self {
+    /// Walks back through the reachable values of each prefix, so every branch it takes leads to
+    /// a solution. `chosen` holds the operators from the end of the equation backwards.
+    fn search_dynamic<'a>(
+        &self,
+        available_operators: &[&'a dyn Operator],
+        layers: &[BTreeMap<Number, usize>],
+        len: usize,
+        target: Number,
+        chosen: &mut Vec<&'a dyn Operator>,
+        visit: &mut dyn FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        if len == 1 {
+            if self.accumulator == target {
+                let operators = chosen.iter().rev().copied().collect::<Vec<_>>();
+                return visit(&operators);
+            }
+            return ControlFlow::Continue(());
+        }
+
+        let last = self.numbers[len - 2];
+        for &previous in layers[len - 2].keys() {
+            for operator in available_operators {
+                if operator.apply(previous, last) == Some(target) {
+                    chosen.push(*operator);
+                    let flow = self.search_dynamic(
+                        available_operators,
+                        layers,
+                        len - 1,
+                        previous,
+                        chosen,
+                        visit,
+                    );
+                    chosen.pop();
+                    flow?;
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Whether a value above the target can never come back down. A zero operand breaks this
+    /// even for operators that never decrease, since `x * 0` is smaller than `x`.
+    fn only_increases(&self, available_operators: &[&dyn Operator]) -> bool {
+        !self.numbers.contains(&0)
+            && available_operators
+                .iter()
+                .all(|operator| operator.never_decreases())
+    }
+
+    /// For each prefix of the numbers, every value it can reach and how many operator sequences
+    /// reach it, saturating on overflow. Values above `bound` are dropped when no operator can
+    /// bring them back down. Equal values are merged, so a layer never holds more distinct
+    /// values than the range allows, however many sequences lead to them.
+    fn layers(
+        &self,
+        available_operators: &[&dyn Operator],
+        bound: Option<Number>,
+    ) -> Vec<BTreeMap<Number, usize>> {
+        let bound = bound.filter(|_| self.only_increases(available_operators));
+        let mut layers = vec![BTreeMap::from([(self.accumulator, 1)])];
+        for &right in &self.numbers {
+            let mut layer = BTreeMap::new();
+            for (&left, &count) in layers.last().unwrap() {
+                for operator in available_operators {
+                    if let Some(value) = operator.apply(left, right)
+                        && bound.is_none_or(|bound| value <= bound)
+                    {
+                        let total: &mut usize = layer.entry(value).or_default();
+                        *total = total.saturating_add(count);
+                    }
+                }
+            }
+            layers.push(layer);
+        }
+        layers
+    }
+
+    /// Every target the numbers can be combined into, whatever the equation's own target is.
+    fn reachable_targets(&self, available_operators: &[&dyn Operator]) -> Vec<Number> {
+        self.layers(available_operators, None)
+            .pop()
+            .unwrap()
+            .into_keys()
+            .collect()
+    }
+
+    fn operate_next(&self, operator: &dyn Operator) -> Option<Self> {
         let mut this = self.clone();
-        this.accumulator = operator.compute(this.accumulator, this.numbers.pop_front().unwrap());
-        this
+        let right = this.numbers.pop_front().unwrap();
+        this.accumulator = operator.apply(this.accumulator, right)?;
+        Some(this)
+    }
+
+    /// Writes the equation out with the operators between its numbers, like `190 = 10 * 19`.
+    fn render(&self, operators: &[&dyn Operator]) -> String {
+        self.numbers.iter().zip(operators).fold(
+            format!("{} = {}", self.target, self.accumulator),
+            |rendered, (number, operator)| format!("{rendered} {} {number}", operator.symbol()),
+        )
     }
 }
 
-enum Operator {
-    Add,
-    Multiply,
-    Concatenate,
+#[derive(Clone, Copy, Debug)]
+enum Solver {
+    /// Tries operators from the first number onwards.
+    Forward,
+    /// Works back from the target with each operator's inverse, as far as operators can be
+    /// undone.
+    Reverse,
+    /// Builds the set of values reachable after each prefix of the numbers, which stays small
+    /// for long equations where the other solvers blow up.
+    Dynamic,
 }
 
-impl Operator {
-    fn compute(&self, left: usize, right: usize) -> usize {
-        match self {
-            Self::Add => left + right,
-            Self::Multiply => left * right,
-            Self::Concatenate => format!("{left}{right}").parse().unwrap(),
+impl Solver {
+    fn from_str(input: &str) -> Self {
+        match input {
+            "forward" => Self::Forward,
+            "reverse" => Self::Reverse,
+            "dynamic" => Self::Dynamic,
+            _ => panic!("Unknown solver {input}!"),
         }
     }
 }
 
+/// A binary operator that can be placed between the numbers of an equation, which are always
+/// combined left to right.
+trait Operator {
+    fn symbol(&self) -> String;
+
+    /// Returns `None` when the operator isn't defined for these operands, including when the
+    /// result would overflow, so the solver drops that branch instead of panicking.
+    fn apply(&self, left: Number, right: Number) -> Option<Number>;
+
+    /// The `left` for which `apply(left, right)` gives `result`, if there is exactly one. Only
+    /// used when `invertible` says it's implemented.
+    fn inverse(&self, _result: Number, _right: Number) -> Option<Number> {
+        None
+    }
+
+    /// Whether `inverse` is implemented. The reverse solver tries every prefix forwards for
+    /// operators that aren't, so leaving both out is slow but never wrong.
+    fn invertible(&self) -> bool {
+        false
+    }
+
+    /// Whether `apply(left, right)` gives `result` for every `left`, like `x * 0 = 0`, which
+    /// `inverse` can't express with a single value.
+    fn absorbs(&self, _result: Number, _right: Number) -> bool {
+        false
+    }
+
+    /// Whether the result is never smaller than `left` for a nonzero `right`, which lets the
+    /// solver stop as soon as it overshoots the target if no operand is zero.
+    fn never_decreases(&self) -> bool {
+        false
+    }
+}
+
+struct Add;
+
+impl Operator for Add {
+    fn symbol(&self) -> String {
+        "+".to_string()
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        left.checked_add(right)
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        result.checked_sub(right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
+struct Multiply;
+
+impl Operator for Multiply {
+    fn symbol(&self) -> String {
+        "*".to_string()
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        left.checked_mul(right)
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        if right != 0 && result.is_multiple_of(right) {
+            Some(result / right)
+        } else {
+            None
+        }
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn absorbs(&self, result: Number, right: Number) -> bool {
+        result == 0 && right == 0
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
+struct Concatenate;
+
+impl Operator for Concatenate {
+    fn symbol(&self) -> String {
+        "||".to_string()
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        BaseConcatenate(10).apply(left, right)
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        BaseConcatenate(10).inverse(result, right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
+struct Subtract;
+
+impl Operator for Subtract {
+    fn symbol(&self) -> String {
+        "-".to_string()
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        left.checked_sub(right)
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        result.checked_add(right)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+}
+
+/// Integer division that is only defined when it has no remainder.
+struct Divide;
+
+impl Operator for Divide {
+    fn symbol(&self) -> String {
+        "/".to_string()
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        if right != 0 && left.is_multiple_of(right) {
+            Some(left / right)
+        } else {
+            None
+        }
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        if right != 0 {
+            result.checked_mul(right)
+        } else {
+            None
+        }
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+}
+
+struct Exponent;
+
+impl Operator for Exponent {
+    fn symbol(&self) -> String {
+        "^".to_string()
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        left.checked_pow(right.try_into().ok()?)
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        // Every `left` gives 1 for a zero exponent, see `absorbs`.
+        let right: u32 = right.try_into().ok().filter(|&right| right > 0)?;
+        let (mut low, mut high) = (0, result);
+        while low <= high {
+            let mid = low + (high - low) / 2;
+            match mid.checked_pow(right) {
+                Some(power) if power == result => return Some(mid),
+                Some(power) if power < result => low = mid + 1,
+                _ => high = mid.checked_sub(1)?,
+            }
+        }
+        None
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn absorbs(&self, result: Number, right: Number) -> bool {
+        result == 1 && right == 0
+    }
+}
+
+/// Concatenates the digits of both operands written in the given base.
+struct BaseConcatenate(Number);
+
+impl BaseConcatenate {
+    /// The smallest power of the base that is larger than `n`, i.e. the factor that shifts
+    /// `left` past all the digits of `n`. This is `None` if that power doesn't fit, in which
+    /// case no positive `left` can be shifted either.
+    fn shift(&self, n: Number) -> Option<Number> {
+        let mut shift = self.0;
+        while shift <= n {
+            shift = shift.checked_mul(self.0)?;
+        }
+        Some(shift)
+    }
+}
+
+impl Operator for BaseConcatenate {
+    fn symbol(&self) -> String {
+        format!("||{}", self.0)
+    }
+
+    fn apply(&self, left: Number, right: Number) -> Option<Number> {
+        left.checked_mul(self.shift(right)?)?.checked_add(right)
+    }
+
+    fn inverse(&self, result: Number, right: Number) -> Option<Number> {
+        let shift = self.shift(right)?;
+        if result % shift == right {
+            Some(result / shift)
+        } else {
+            None
+        }
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     fn test_part_1() {
-        assert_eq!(part_1(INPUT), 1038838357795)
+        assert_eq!(part_1(INPUT, Solver::Reverse), 1038838357795)
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(part_2(INPUT), 254136560217241)
+        assert_eq!(part_2(INPUT, Solver::Reverse), 254136560217241)
+    }
+
+    #[test]
+    fn test_parts_with_each_solver() {
+        for solver in [Solver::Forward, Solver::Reverse, Solver::Dynamic] {
+            assert_eq!(part_1(EXAMPLE, solver), 3749);
+            assert_eq!(part_2(EXAMPLE, solver), 11387);
+        }
+    }
+
+    #[test]
+    fn test_custom_operators() {
+        for solver in [Solver::Forward, Solver::Reverse] {
+            let eq = Equation::from_str("5: 12 3 4");
+            assert!(eq.is_true(&[&Subtract, &Divide], solver));
+            assert!(!eq.is_true(&[&Add, &Multiply], solver));
+
+            assert!(Equation::from_str("81: 3 2 2").is_true(&[&Exponent], solver));
+            assert!(Equation::from_str("13: 1 1 0 1").is_true(&[&BaseConcatenate(2)], solver));
+        }
+
+        let operators = parse_operators("+,*,||");
+        let operators = operators.iter().map(Box::as_ref).collect::<Vec<_>>();
+        assert_eq!(calibration(EXAMPLE, &operators, Solver::Forward), 11387);
+    }
+
+    #[test]
+    fn test_operator_without_inverse() {
+        struct Max;
+
+        impl Operator for Max {
+            fn symbol(&self) -> String {
+                "max".to_string()
+            }
+
+            fn apply(&self, left: Number, right: Number) -> Option<Number> {
+                Some(left.max(right))
+            }
+        }
+
+        let operators: [&dyn Operator; 2] = [&Add, &Max];
+        for (input, n_solutions) in [("7: 3 7 2", 1), ("9: 3 7 2", 1), ("12: 3 7 2", 1)] {
+            let eq = Equation::from_str(input);
+            assert_eq!(eq.count_solutions(&operators, Solver::Forward), n_solutions);
+            assert_eq!(eq.count_solutions(&operators, Solver::Reverse), n_solutions);
+            assert_eq!(eq.count_solutions(&operators, Solver::Dynamic), n_solutions);
+        }
+        assert_eq!(
+            calibration("7: 3 7 2\n9: 3 7 2\n8: 3 7 2", &operators, Solver::Reverse),
+            16
+        );
+    }
+
+    #[test]
+    fn test_zero_operands() {
+        let divide: [&dyn Operator; 1] = [&Divide];
+        let eq = Equation::from_str("0: 0 0");
+        assert_eq!(eq.count_solutions(&divide, Solver::Reverse), 0);
+        assert_eq!(eq.count_solutions(&divide, Solver::Dynamic), 0);
+
+        // A trailing `* 0` or `^ 0` reaches the target from whatever comes before it.
+        let operators: [&dyn Operator; 3] = [&Add, &Multiply, &Exponent];
+        for (input, n_solutions) in [("0: 3 4 0", 3), ("1: 3 4 0", 3), ("0: 0 0", 2)] {
+            let eq = Equation::from_str(input);
+            assert_eq!(eq.count_solutions(&operators, Solver::Forward), n_solutions);
+            assert_eq!(eq.count_solutions(&operators, Solver::Reverse), n_solutions);
+            assert_eq!(eq.count_solutions(&operators, Solver::Dynamic), n_solutions);
+        }
+    }
+
+    #[test]
+    fn test_solutions() {
+        let operators: [&dyn Operator; 3] = [&Add, &Multiply, &Concatenate];
+
+        let eq = Equation::from_str("190: 10 19");
+        assert_eq!(
+            eq.render(&eq.solve(&operators, Solver::Forward).unwrap()),
+            "190 = 10 * 19"
+        );
+
+        let eq = Equation::from_str("7290: 6 8 6 15");
+        assert_eq!(
+            eq.render(&eq.solve(&operators, Solver::Forward).unwrap()),
+            "7290 = 6 * 8 || 6 * 15"
+        );
+
+        let eq = Equation::from_str("3267: 81 40 27");
+        let rendered = eq
+            .solutions(&operators, Solver::Forward)
+            .iter()
+            .map(|solution| eq.render(solution))
+            .collect::<Vec<_>>();
+        assert_eq!(rendered, vec!["3267 = 81 + 40 * 27", "3267 = 81 * 40 + 27"]);
+        assert_eq!(eq.count_solutions(&operators, Solver::Forward), 2);
+
+        let eq = Equation::from_str("83: 17 5");
+        assert!(eq.solve(&operators, Solver::Forward).is_none());
+        assert_eq!(eq.count_solutions(&operators, Solver::Forward), 0);
     }
+
+    #[test]
+    fn test_solvers_agree() {
+        let operators: [&dyn Operator; 5] = [&Add, &Multiply, &Concatenate, &Subtract, &Divide];
+        for eq in EXAMPLE.lines().chain(["0: 5 0"]).map(Equation::from_str) {
+            let forward = eq.solutions(&operators, Solver::Forward);
+            let reverse = eq.solutions(&operators, Solver::Reverse);
+            let dynamic = eq.solutions(&operators, Solver::Dynamic);
+            let render = |solutions: Vec<Vec<&dyn Operator>>| {
+                let mut rendered = solutions
+                    .iter()
+                    .map(|solution| eq.render(solution))
+                    .collect::<Vec<_>>();
+                rendered.sort();
+                rendered
+            };
+            assert_eq!(
+                eq.count_solutions(&operators, Solver::Dynamic),
+                forward.len()
+            );
+            let forward = render(forward);
+            assert_eq!(forward, render(reverse));
+            assert_eq!(forward, render(dynamic));
+        }
+    }
+
+    #[test]
+    fn test_wide_and_overflowing_numbers() {
+        let operators: [&dyn Operator; 3] = [&Add, &Multiply, &Concatenate];
+        for solver in [Solver::Forward, Solver::Reverse] {
+            let eq = Equation::from_str("36893488147419103232: 18446744073709551616 2");
+            assert!(eq.is_true(&operators, solver));
+
+            let eq = Equation::from_str("1: 340282366920938463463374607431768211455 2 3");
+            assert!(!eq.is_true(&operators, solver));
+        }
+        assert_eq!(Concatenate.apply(12, 345), Some(12345));
+        assert_eq!(Concatenate.apply(Number::MAX / 10, 99), None);
+        assert_eq!(Exponent.inverse(3_u128.pow(80), 80), Some(3));
+    }
+
+    #[test]
+    fn test_dynamic_long_equation() {
+        let ones = vec!["1"; 60].join(" ");
+        let eq = Equation::from_str(&format!("60: {ones}"));
+        assert!(eq.is_true(&[&Add, &Multiply, &Concatenate], Solver::Dynamic));
+        assert_eq!(eq.count_solutions(&[&Add, &Multiply], Solver::Dynamic), 1);
+
+        let eq = Equation::from_str("0: 2 3 4");
+        assert_eq!(
+            eq.reachable_targets(&[&Add, &Multiply]),
+            vec![9, 10, 20, 24]
+        );
+    }
+
+    const EXAMPLE: &str = r#"190: 10 19
+3267: 81 40 27
+83: 17 5
+156: 15 6
+7290: 6 8 6 15
+161011: 16 10 13
+192: 17 8 14
+21037: 9 7 18 13
+292: 11 6 16 20"#;
 }
 
 #[allow(clippy::items_after_test_module)]
@@ -0,0 +1,20 @@
+#[derive(Debug)]
+pub struct Args(Vec<String>);
+
+impl Args {
+    pub fn from_env() -> Self {
+        Self(std::env::args().skip(1).collect())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.0.iter().any(|arg| arg == name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .position(|arg| arg == name)
+            .and_then(|idx| self.0.get(idx + 1))
+            .map(String::as_str)
+    }
+}
@@ -1,6 +1,6 @@
 use std::ops::Range;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Array2D<T> {
     n_rows: usize,
     n_cols: usize,
@@ -33,6 +33,10 @@ impl<T> Array2D<T> {
         self.n_cols
     }
 
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        (row < self.n_rows && col < self.n_cols).then(|| &self[row][col])
+    }
+
     pub fn rows(&self) -> impl Iterator<Item = Vec<&T>> {
         (0..self.n_rows).map(|row_idx| {
             (0..self.n_cols)
@@ -74,6 +78,29 @@ impl<T> Array2D<T> {
     }
 }
 
+impl<T: Clone> Array2D<T> {
+    /// A quarter turn clockwise, so the first column becomes the first row read bottom up.
+    pub fn rotated(&self) -> Self {
+        let data = (0..self.n_cols)
+            .flat_map(|col_idx| {
+                (0..self.n_rows)
+                    .rev()
+                    .map(move |row_idx| (row_idx, col_idx))
+            })
+            .map(|(row_idx, col_idx)| self[row_idx][col_idx].clone())
+            .collect();
+        Self::from_row_major_vec(data, (self.n_cols, self.n_rows))
+    }
+
+    /// Reflected left to right.
+    pub fn mirrored(&self) -> Self {
+        let data = (0..self.n_rows)
+            .flat_map(|row_idx| self[row_idx].iter().rev().cloned())
+            .collect();
+        Self::from_row_major_vec(data, (self.n_rows, self.n_cols))
+    }
+}
+
 impl<T> Array2D<T>
 where
     T: for<'a> std::iter::Sum<&'a T>,
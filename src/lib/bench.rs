@@ -0,0 +1,11 @@
+use std::time::{Duration, Instant};
+
+pub fn bench<R>(label: &str, iterations: u32, mut func: impl FnMut() -> R) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        std::hint::black_box(func());
+    }
+    let per_iteration = start.elapsed() / iterations;
+    println!("{label}: {per_iteration:?}/iter");
+    per_iteration
+}
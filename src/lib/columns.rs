@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+/// Parses each non-blank line of `input` as numbers separated by any amount of whitespace, one
+/// line at a time. A bad line doesn't stop the lines after it from being parsed.
+pub fn parse_rows<T: FromStr>(input: &str) -> impl Iterator<Item = Result<Vec<T>, ParseError>> {
+    numbered_lines(input).map(|(line, text)| {
+        text.split_whitespace()
+            .enumerate()
+            .map(|(column, field)| parse_field(line, column, field))
+            .collect()
+    })
+}
+
+/// Parses `input` as exactly `N` whitespace separated columns of numbers, pushing each number
+/// straight onto its column. Stops at the first line that is malformed.
+pub fn parse_columns<T: FromStr, const N: usize>(input: &str) -> Result<[Vec<T>; N], ParseError> {
+    let mut columns = std::array::from_fn(|_| Vec::new());
+    for (line, text) in numbered_lines(input) {
+        let mut fields = text.split_whitespace();
+        for (column, values) in columns.iter_mut().enumerate() {
+            let field = fields.next().ok_or(ParseError::Columns {
+                line,
+                expected: N,
+                found: column,
+            })?;
+            values.push(parse_field(line, column, field)?);
+        }
+        let extra = fields.count();
+        if extra > 0 {
+            return Err(ParseError::Columns {
+                line,
+                expected: N,
+                found: N + extra,
+            });
+        }
+    }
+    Ok(columns)
+}
+
+/// Lines and columns are numbered from 1, like in an editor.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Columns {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    Number {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Columns {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: expected {expected} column(s), found {found}"
+            ),
+            Self::Number { line, column, text } => {
+                write!(
+                    f,
+                    "line {line}, column {column}: `{text}` isn't a valid number"
+                )
+            }
+        }
+    }
+}
+
+fn numbered_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(idx, text)| (idx + 1, text))
+        .filter(|(_, text)| !text.trim().is_empty())
+}
+
+fn parse_field<T: FromStr>(line: usize, column: usize, field: &str) -> Result<T, ParseError> {
+    field.parse().map_err(|_| ParseError::Number {
+        line,
+        column: column + 1,
+        text: field.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_columns() {
+        let columns: Result<[Vec<u64>; 2], _> = parse_columns("3   4\n4\t3\n  2 5  \n\n");
+        assert_eq!(columns, Ok([vec![3, 4, 2], vec![4, 3, 5]]));
+
+        let columns: Result<[Vec<u64>; 2], _> = parse_columns("3 4\n4 3 1\n");
+        assert_eq!(
+            columns,
+            Err(ParseError::Columns {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        );
+        let columns: Result<[Vec<u64>; 2], _> = parse_columns("3 4\n\n4\n");
+        assert_eq!(
+            columns,
+            Err(ParseError::Columns {
+                line: 3,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rows() {
+        let rows: Vec<Result<Vec<u64>, _>> = parse_rows("1 2 3\n4 x\n\n5").collect();
+        assert_eq!(
+            rows,
+            vec![
+                Ok(vec![1, 2, 3]),
+                Err(ParseError::Number {
+                    line: 2,
+                    column: 2,
+                    text: "x".to_string()
+                }),
+                Ok(vec![5]),
+            ]
+        );
+    }
+}
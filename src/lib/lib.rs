@@ -1,5 +1,17 @@
+mod args;
 mod array;
+mod bench;
+mod columns;
+mod parallel;
+mod pattern;
 mod queue;
+mod search;
 
+pub use args::Args;
 pub use array::Array2D;
+pub use bench::bench;
+pub use columns::{ParseError, parse_columns, parse_rows};
+pub use parallel::{Parallel, ParallelError};
+pub use pattern::Pattern;
 pub use queue::FixedSizeQueue;
+pub use search::{Direction, Match, WordSearch};
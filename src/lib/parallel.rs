@@ -0,0 +1,191 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Runs a function over a slice on several threads. Threads claim chunks of the slice in order
+/// from a shared counter, so fast threads keep taking work while slow ones finish theirs, and
+/// results always come back in the order of the slice.
+#[derive(Debug)]
+pub struct Parallel {
+    n_threads: usize,
+    chunk_size: Option<usize>,
+}
+
+impl Parallel {
+    pub fn new() -> Self {
+        Self {
+            n_threads: std::thread::available_parallelism().map_or(1, usize::from),
+            chunk_size: None,
+        }
+    }
+
+    /// With a single thread everything runs on the calling thread, which keeps backtraces simple.
+    pub fn with_threads(self, n_threads: usize) -> Self {
+        assert!(n_threads > 0, "Need at least one thread!");
+        Self { n_threads, ..self }
+    }
+
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "Chunks can't be empty!");
+        Self {
+            chunk_size: Some(chunk_size),
+            ..self
+        }
+    }
+
+    /// Stops claiming new chunks after the first error or panic. Chunks are claimed in order, so
+    /// every item before the failing one has still been processed, and the error returned is
+    /// always the one for the earliest failing item.
+    pub fn map<T, R, E, F>(&self, items: &[T], func: F) -> Result<Vec<R>, ParallelError<E>>
+    where
+        T: Sync,
+        R: Send,
+        E: Send,
+        F: Fn(&T) -> Result<R, E> + Sync,
+    {
+        let chunk_size = self
+            .chunk_size
+            .unwrap_or(std::cmp::max(1, items.len() / (self.n_threads * 4)));
+        let n_chunks = items.len().div_ceil(chunk_size);
+        let next_chunk = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+
+        let run_item = |index: usize, item: &T| match std::panic::catch_unwind(
+            std::panic::AssertUnwindSafe(|| func(item)),
+        ) {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(error)) => Err(ParallelError::Task { index, error }),
+            Err(payload) => Err(ParallelError::Panic {
+                index,
+                message: panic_message(payload),
+            }),
+        };
+        let worker = || {
+            let mut done = Vec::new();
+            while !failed.load(Ordering::Relaxed) {
+                let chunk_idx = next_chunk.fetch_add(1, Ordering::Relaxed);
+                if chunk_idx >= n_chunks {
+                    break;
+                }
+                let start = chunk_idx * chunk_size;
+                let end = std::cmp::min(start + chunk_size, items.len());
+                let results = items[start..end]
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, item)| run_item(start + offset, item))
+                    .collect::<Result<Vec<_>, _>>();
+                if results.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+                done.push((chunk_idx, results));
+            }
+            done
+        };
+
+        let mut chunks = if self.n_threads == 1 {
+            worker()
+        } else {
+            std::thread::scope(|scope| {
+                let handles = (0..self.n_threads)
+                    .map(|_| scope.spawn(worker))
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("Panics are caught per item!"))
+                    .collect::<Vec<_>>()
+            })
+        };
+        chunks.sort_by_key(|(chunk_idx, _)| *chunk_idx);
+
+        let mut output = Vec::with_capacity(items.len());
+        for (_, results) in chunks {
+            output.extend(results?);
+        }
+        Ok(output)
+    }
+
+    pub fn map_reduce<T, R, E, A, F, G>(
+        &self,
+        items: &[T],
+        func: F,
+        init: A,
+        reduce: G,
+    ) -> Result<A, ParallelError<E>>
+    where
+        T: Sync,
+        R: Send,
+        E: Send,
+        F: Fn(&T) -> Result<R, E> + Sync,
+        G: FnMut(A, R) -> A,
+    {
+        Ok(self.map(items, func)?.into_iter().fold(init, reduce))
+    }
+}
+
+impl Default for Parallel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParallelError<E> {
+    Task { index: usize, error: E },
+    Panic { index: usize, message: String },
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for ParallelError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Task { index, error } => write!(f, "item {index} failed: {error}"),
+            Self::Panic { index, message } => write!(f, "item {index} panicked: {message}"),
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_map_is_ordered() {
+        let items = (0..1_000).collect::<Vec<usize>>();
+        for n_threads in [1, 3, 8] {
+            let squares = Parallel::new()
+                .with_threads(n_threads)
+                .with_chunk_size(7)
+                .map(&items, |n| Ok::<_, ()>(n * n))
+                .unwrap();
+            assert_eq!(squares, items.iter().map(|n| n * n).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_map_reports_first_failure() {
+        let items = (0..1_000).collect::<Vec<usize>>();
+        let result = Parallel::new()
+            .with_threads(4)
+            .with_chunk_size(10)
+            .map(&items, |&n| {
+                if n == 500 {
+                    panic!("boom");
+                }
+                if n % 300 == 299 { Err(n) } else { Ok(n) }
+            });
+        assert_eq!(
+            result,
+            Err(ParallelError::Task {
+                index: 299,
+                error: 299
+            })
+        );
+    }
+}
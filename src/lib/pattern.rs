@@ -0,0 +1,120 @@
+use crate::Array2D;
+
+/// A small rectangular shape to look for in a grid. Cells holding `None` match anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern<T> {
+    cells: Array2D<Option<T>>,
+}
+
+impl Pattern<char> {
+    /// Reads a template like `M.S / .A. / M.S`, with rows separated by `/` or newlines and `.`
+    /// as the wildcard. Whitespace around rows is ignored.
+    pub fn parse(template: &str) -> Self {
+        let rows: Vec<&str> = template
+            .split(['/', '\n'])
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        assert!(
+            rows.windows(2).all(|pair| pair[0].len() == pair[1].len()),
+            "Pattern rows must all have the same length!"
+        );
+        Self::new(Array2D::from_rows(rows.into_iter().map(|row| {
+            row.chars().map(|letter| (letter != '.').then_some(letter))
+        })))
+    }
+}
+
+impl<T> Pattern<T> {
+    pub fn new(cells: Array2D<Option<T>>) -> Self {
+        Self { cells }
+    }
+
+    pub fn n_rows(&self) -> usize {
+        self.cells.n_rows()
+    }
+
+    pub fn n_cols(&self) -> usize {
+        self.cells.n_cols()
+    }
+
+    /// The cells that aren't wildcards, relative to the top left corner.
+    pub fn fixed_cells(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        (0..self.n_rows()).flat_map(move |row| {
+            (0..self.n_cols())
+                .filter_map(move |col| self.cells[row][col].as_ref().map(|cell| ((row, col), cell)))
+        })
+    }
+}
+
+impl<T: PartialEq> Pattern<T> {
+    pub fn matches_at(&self, grid: &Array2D<T>, top_left: (usize, usize)) -> bool {
+        self.fixed_cells()
+            .all(|((row, col), cell)| grid.get(top_left.0 + row, top_left.1 + col) == Some(cell))
+    }
+
+    /// The top left corner of every place the pattern fits in the grid, in reading order.
+    pub fn find<'a>(&'a self, grid: &'a Array2D<T>) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let n_rows = (grid.n_rows() + 1).saturating_sub(self.n_rows());
+        let n_cols = (grid.n_cols() + 1).saturating_sub(self.n_cols());
+        (0..n_rows)
+            .flat_map(move |row| (0..n_cols).map(move |col| (row, col)))
+            .filter(move |&top_left| self.matches_at(grid, top_left))
+    }
+}
+
+impl<T: Clone + PartialEq> Pattern<T> {
+    /// Every distinct rotation and reflection of the pattern, starting with the pattern itself.
+    /// Symmetric patterns have fewer than eight, so no match is found twice.
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut orientations: Vec<Self> = Vec::with_capacity(8);
+        let mut cells = self.cells.clone();
+        for _ in 0..4 {
+            for candidate in [cells.clone(), cells.mirrored()] {
+                let candidate = Self::new(candidate);
+                if !orientations.contains(&candidate) {
+                    orientations.push(candidate);
+                }
+            }
+            cells = cells.rotated();
+        }
+        orientations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_orientations() {
+        let x_mas = Pattern::parse("M.S / .A. / M.S");
+        let orientations = x_mas.orientations();
+        assert_eq!(
+            orientations,
+            vec![
+                x_mas.clone(),
+                Pattern::parse("S.M / .A. / S.M"),
+                Pattern::parse("M.M / .A. / S.S"),
+                Pattern::parse("S.S / .A. / M.M"),
+            ]
+        );
+
+        let corner = Pattern::parse("AB\nC.");
+        assert_eq!(corner.orientations().len(), 8);
+    }
+
+    #[test]
+    fn test_find() {
+        let grid = Array2D::from_rows(["M.S.M", ".A.A.", "M.S.M"].map(str::chars));
+        let x_mas = Pattern::parse("M.S / .A. / M.S");
+        assert_eq!(x_mas.find(&grid).collect::<Vec<_>>(), vec![(0, 0)]);
+
+        let matches: Vec<_> = x_mas
+            .orientations()
+            .iter()
+            .flat_map(|pattern| pattern.find(&grid).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(matches, vec![(0, 0), (0, 2)]);
+    }
+}
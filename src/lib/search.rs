@@ -0,0 +1,219 @@
+use crate::Array2D;
+
+/// A compass direction on a grid, with north pointing towards row 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+    pub const ORTHOGONAL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+    pub const DIAGONAL: [Direction; 4] = [
+        Self::NorthEast,
+        Self::SouthEast,
+        Self::SouthWest,
+        Self::NorthWest,
+    ];
+
+    /// The (row, col) step taken when moving one cell in this direction.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Self::North => (-1, 0),
+            Self::NorthEast => (-1, 1),
+            Self::East => (0, 1),
+            Self::SouthEast => (1, 1),
+            Self::South => (1, 0),
+            Self::SouthWest => (1, -1),
+            Self::West => (0, -1),
+            Self::NorthWest => (-1, -1),
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::NorthEast => Self::SouthWest,
+            Self::East => Self::West,
+            Self::SouthEast => Self::NorthWest,
+            Self::South => Self::North,
+            Self::SouthWest => Self::NorthEast,
+            Self::West => Self::East,
+            Self::NorthWest => Self::SouthEast,
+        }
+    }
+
+    /// The cell `steps` cells away from `from`, if it isn't off the top or left of the grid.
+    pub fn step(self, from: (usize, usize), steps: usize) -> Option<(usize, usize)> {
+        let (d_row, d_col) = self.offset();
+        let row = from.0.checked_add_signed(d_row * steps as isize)?;
+        let col = from.1.checked_add_signed(d_col * steps as isize)?;
+        Some((row, col))
+    }
+}
+
+/// A word found in the grid, reading `len` cells from `start` in `direction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Match {
+    pub start: (usize, usize),
+    pub direction: Direction,
+    pub len: usize,
+}
+
+impl Match {
+    /// The cells covered by the word, in reading order.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> {
+        let Self {
+            start, direction, ..
+        } = self;
+        // Matches only cover cells inside the grid, so every step exists.
+        (0..self.len).map(move |steps| direction.step(start, steps).unwrap())
+    }
+}
+
+/// Searches a grid for words written in straight lines along a fixed set of directions.
+#[derive(Debug, Clone)]
+pub struct WordSearch {
+    directions: Vec<Direction>,
+}
+
+impl WordSearch {
+    pub fn new(directions: impl IntoIterator<Item = Direction>) -> Self {
+        let mut directions: Vec<_> = directions.into_iter().collect();
+        directions.sort();
+        directions.dedup();
+        Self { directions }
+    }
+
+    /// Also finds words written backwards along each direction.
+    pub fn with_reversal(self) -> Self {
+        let reversed = self.directions.iter().map(|direction| direction.reverse());
+        Self::new(self.directions.iter().copied().chain(reversed))
+    }
+
+    pub fn directions(&self) -> &[Direction] {
+        &self.directions
+    }
+
+    /// Every match of `word`, ordered by start cell and then direction. A palindrome matches
+    /// once in each of two opposite directions if both are searched, but a single letter reads
+    /// the same every way, so it only matches once per cell, along the first direction.
+    pub fn find<'a, T: PartialEq>(
+        &'a self,
+        grid: &'a Array2D<T>,
+        word: &'a [T],
+    ) -> impl Iterator<Item = Match> + 'a {
+        let directions = if word.len() == 1 {
+            &self.directions[..self.directions.len().min(1)]
+        } else {
+            &self.directions[..]
+        };
+        let starts =
+            (0..grid.n_rows()).flat_map(|row| (0..grid.n_cols()).map(move |col| (row, col)));
+        starts
+            .filter(move |&(row, col)| word.first().is_some_and(|first| grid[row][col] == *first))
+            .flat_map(move |start| {
+                directions.iter().filter_map(move |&direction| {
+                    let found = word.iter().enumerate().skip(1).all(|(steps, letter)| {
+                        direction
+                            .step(start, steps)
+                            .and_then(|(row, col)| grid.get(row, col))
+                            .is_some_and(|cell| cell == letter)
+                    });
+                    found.then_some(Match {
+                        start,
+                        direction,
+                        len: word.len(),
+                    })
+                })
+            })
+    }
+
+    pub fn count<T: PartialEq>(&self, grid: &Array2D<T>, word: &[T]) -> usize {
+        self.find(grid, word).count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid(rows: &str) -> Array2D<char> {
+        Array2D::from_rows(rows.split_whitespace().map(str::chars))
+    }
+
+    #[test]
+    fn test_find() {
+        let grid = grid("..X... .SAMX. .A..A. XMAS.S .X....");
+        let word: Vec<_> = "XMAS".chars().collect();
+
+        let matches: Vec<_> = WordSearch::new(Direction::ALL).find(&grid, &word).collect();
+        assert_eq!(
+            matches,
+            vec![
+                Match {
+                    start: (0, 2),
+                    direction: Direction::SouthEast,
+                    len: 4,
+                },
+                Match {
+                    start: (1, 4),
+                    direction: Direction::West,
+                    len: 4,
+                },
+                Match {
+                    start: (3, 0),
+                    direction: Direction::East,
+                    len: 4,
+                },
+                Match {
+                    start: (4, 1),
+                    direction: Direction::North,
+                    len: 4,
+                },
+            ]
+        );
+        assert_eq!(
+            matches[0].cells().collect::<Vec<_>>(),
+            vec![(0, 2), (1, 3), (2, 4), (3, 5)]
+        );
+
+        let forward = WordSearch::new([Direction::East, Direction::South]);
+        assert_eq!(forward.count(&grid, &word), 1);
+        assert_eq!(forward.clone().with_reversal().count(&grid, &word), 3);
+        assert_eq!(forward.with_reversal().directions(), &Direction::ORTHOGONAL);
+    }
+
+    #[test]
+    fn test_find_letter() {
+        let grid = grid("..X... .SAMX. .A..A. XMAS.S .X....");
+        let matches: Vec<_> = WordSearch::new(Direction::ALL)
+            .find(&grid, &['X'])
+            .collect();
+        assert_eq!(
+            matches,
+            [(0, 2), (1, 4), (3, 0), (4, 1)].map(|start| Match {
+                start,
+                direction: Direction::North,
+                len: 1,
+            })
+        );
+        assert_eq!(WordSearch::new([]).count(&grid, &['X']), 0);
+    }
+}